@@ -1,2 +1 @@
-LAST_LINE=`tail -1 requests.txt`
-sh -c "${LAST_LINE}"
+target/release/skyreport queue run
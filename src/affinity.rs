@@ -0,0 +1,225 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::config::Config;
+use crate::DynResult;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::mem;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A set of CPUs, written as a CPU list (for example, `0-3,6`) like in `taskset -c`
+pub struct CpuSet {
+    cpus: Vec<usize>,
+}
+
+impl CpuSet {
+    /// Parse a CPU list such as `0-3,6`
+    pub fn parse(input: &str) -> DynResult<Self> {
+        let parse_cpu = |cpu: &str| -> DynResult<usize> {
+            let cpu: usize = cpu
+                .trim()
+                .parse()
+                .map_err(|_| format!("`{}` is not a valid CPU", cpu))?;
+            if cpu >= libc::CPU_SETSIZE as usize {
+                return rerr!(format!("CPU {} is out of range", cpu));
+            }
+            Ok(cpu)
+        };
+        let mut cpus = Vec::new();
+        for range in input.split(',') {
+            match range.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (parse_cpu(start)?, parse_cpu(end)?);
+                    if start > end {
+                        return rerr!(format!("`{}` is not a valid CPU range", range));
+                    }
+                    cpus.extend(start..=end);
+                }
+                None => cpus.push(parse_cpu(range)?),
+            }
+        }
+        cpus.sort_unstable();
+        cpus.dedup();
+        Ok(Self { cpus })
+    }
+    /// Returns the CPUs that this process is allowed to run on
+    pub fn available() -> DynResult<Self> {
+        // a zeroed set is an empty set
+        let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+        if unsafe { libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut set) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        let cpus = (0..libc::CPU_SETSIZE as usize)
+            .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+            .collect();
+        Ok(Self { cpus })
+    }
+    pub fn is_subset(&self, other: &CpuSet) -> bool {
+        self.cpus.iter().all(|cpu| other.cpus.contains(cpu))
+    }
+    pub fn overlaps(&self, other: &CpuSet) -> bool {
+        self.cpus.iter().any(|cpu| other.cpus.contains(cpu))
+    }
+    fn to_cpu_set(&self) -> libc::cpu_set_t {
+        let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+        self.cpus
+            .iter()
+            .for_each(|&cpu| unsafe { libc::CPU_SET(cpu, &mut set) });
+        set
+    }
+    /// Pin the process that `cmd` spawns to this set of CPUs
+    pub fn apply(&self, cmd: &mut Command) {
+        let set = self.to_cpu_set();
+        let pin = move || {
+            // this runs in the child, after the fork, so only the child is pinned
+            if unsafe { libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        };
+        // sched_setaffinity is async-signal-safe, so it can be called after the fork
+        unsafe { cmd.pre_exec(pin) };
+    }
+    /// Pin the calling thread to this set of CPUs
+    pub fn pin_current_thread(&self) -> io::Result<()> {
+        let set = self.to_cpu_set();
+        if unsafe { libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &set) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CpuSet {
+    /// Writes the set as a CPU list, collapsing consecutive CPUs into ranges
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        for &cpu in &self.cpus {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == cpu => *end = cpu,
+                _ => ranges.push((cpu, cpu)),
+            }
+        }
+        let ranges: Vec<String> = ranges
+            .into_iter()
+            .map(|(start, end)| match start == end {
+                true => start.to_string(),
+                false => format!("{}-{}", start, end),
+            })
+            .collect();
+        write!(f, "{}", ranges.join(","))
+    }
+}
+
+/// Parse the configured CPU list (if any), checking that the CPUs are available
+fn configured(cpus: &Option<String>, what: &str) -> DynResult<Option<CpuSet>> {
+    let cpus = match cpus {
+        Some(cpus) => CpuSet::parse(cpus).map_err(|e| format!("Bad {} CPUs: {}", what, e))?,
+        None => return Ok(None),
+    };
+    let available = CpuSet::available()?;
+    if !cpus.is_subset(&available) {
+        return rerr!(format!(
+            "The {} CPUs `{}` aren't all available (available: `{}`)",
+            what, cpus, available
+        ));
+    }
+    Ok(Some(cpus))
+}
+
+/// Returns the CPUs that `skyd` should be pinned to, if any
+pub fn server_cpus(cfg: &Config) -> DynResult<Option<CpuSet>> {
+    configured(&cfg.affinity.server, "server")
+}
+
+/// Returns the CPUs that `sky-bench` should be pinned to, if any
+pub fn client_cpus(cfg: &Config) -> DynResult<Option<CpuSet>> {
+    configured(&cfg.affinity.client, "client")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// The CPUs that the server and the client ran on
+pub struct Topology {
+    /// The CPUs available to the benchmark
+    pub available: String,
+    /// The CPUs that `skyd` was pinned to (or `None` if it wasn't pinned)
+    pub server: Option<String>,
+    /// The CPUs that the client was pinned to (or `None` if it wasn't pinned)
+    pub client: Option<String>,
+}
+
+impl Topology {
+    /// Returns the topology for the configured affinity, warning if the server and the
+    /// client share CPUs
+    pub fn current(cfg: &Config) -> DynResult<Self> {
+        let (server, client) = (server_cpus(cfg)?, client_cpus(cfg)?);
+        if let (Some(server), Some(client)) = (&server, &client) {
+            if server.overlaps(client) {
+                warn!(
+                    "The server (`{}`) and the client (`{}`) share CPUs",
+                    server, client
+                );
+            }
+        }
+        Ok(Self {
+            available: CpuSet::available()?.to_string(),
+            server: server.map(|cpus| cpus.to_string()),
+            client: client.map(|cpus| cpus.to_string()),
+        })
+    }
+    /// Returns a description of the topology for the report
+    pub fn summary(&self) -> String {
+        let pinned = |cpus: &Option<String>| match cpus {
+            Some(cpus) => format!("`{}`", cpus),
+            None => "any CPU".to_owned(),
+        };
+        format!(
+            "skyd on {}, the client on {} (available: `{}`)",
+            pinned(&self.server),
+            pinned(&self.client),
+            self.available
+        )
+    }
+}
+
+#[test]
+fn test_cpu_set() {
+    let set = CpuSet::parse("6,0-3, 2").unwrap();
+    assert_eq!(set.cpus, vec![0, 1, 2, 3, 6]);
+    assert_eq!(set.to_string(), "0-3,6");
+    assert!(CpuSet::parse("3-1").is_err());
+    assert!(CpuSet::parse("a").is_err());
+    assert!(CpuSet::parse("4096").is_err());
+    assert!(set.overlaps(&CpuSet::parse("3-5").unwrap()));
+    assert!(!set.overlaps(&CpuSet::parse("4-5").unwrap()));
+    // we can always run on the CPUs that we are running on
+    let available = CpuSet::available().unwrap();
+    let mut cmd = Command::new("true");
+    available.apply(&mut cmd);
+    assert!(cmd.status().unwrap().success());
+}
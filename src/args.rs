@@ -22,30 +22,165 @@
  *
 */
 
+use crate::history::Timestamp;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::env;
+use std::path::PathBuf;
 
-macro_rules! nxiter {
-    ($iter:expr, $emsg:expr) => {
-        $iter.next().unwrap_or_else(|| err!($emsg))
-    };
+/// Skyreport: the performance analysis tool for Skytable
+#[derive(Debug, Parser)]
+#[clap(name = "skyreport", version)]
+struct Cli {
+    /// The configuration file to use (defaults to `skyreport.toml`, if present)
+    #[clap(long, global = true, value_parser)]
+    config: Option<PathBuf>,
+    /// Where the results are published to
+    #[clap(long, global = true, value_enum, default_value_t = PublishMode::Github)]
+    publish: PublishMode,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum PublishMode {
+    /// Commit and push the results to the perf repo and comment on the pull request
+    Github,
+    /// Only write the results to the local directory
+    Local,
+    /// Don't write anything; only log what would have been done
+    DryRun,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Metric {
+    Get,
+    Set,
+    Update,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 3] = [Metric::Get, Metric::Set, Metric::Update];
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Metric::Get => "GET",
+            Metric::Set => "SET",
+            Metric::Update => "UPDATE",
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Benchmark a commit (or tag, or branch) and publish the results
+    Bench {
+        /// The commit (or any other git ref) to benchmark
+        #[clap(value_parser = parse_ref)]
+        commit: String,
+        /// The ID of the pull request that the commit belongs to, if any. The results
+        /// are posted to the pull request
+        #[clap(value_parser)]
+        pull: Option<u16>,
+        /// Bench the latest commit on `next` in the same session, alternating with the
+        /// commit, and compare against it using only the paired runs
+        #[clap(long)]
+        interleaved: bool,
+    },
+    /// Benchmark two refs on this machine and print the comparison (nothing is published)
+    Compare {
+        /// The ref to compare against (for example, `next`)
+        #[clap(value_parser = parse_ref)]
+        base: String,
+        /// The ref with the changes that are to be evaluated
+        #[clap(value_parser = parse_ref)]
+        candidate: String,
+        /// Alternate between the base and the candidate, and compare them using only
+        /// the paired runs
+        #[clap(long)]
+        interleaved: bool,
+    },
+    /// Update one of the preset results
+    #[clap(subcommand)]
+    Update(UpdateTarget),
+    /// Show the stored results
+    History(HistoryFilter),
+    /// Check the stored presets, results and reports for problems
+    Validate(ValidateOptions),
+    /// Process the requests in `requests.txt`
+    #[clap(subcommand)]
+    Queue(QueueCommand),
+    /// Manage the cache of built binaries
+    #[clap(subcommand)]
+    Cache(CacheCommand),
+}
+
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum QueueCommand {
+    /// Run every pending request, in order
+    Run,
+    /// List the pending requests
+    List,
+    /// Mark every pending request as processed without running it
+    Skip,
+}
+
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum CacheCommand {
+    /// List the cached builds, most recently used first
+    List,
+    /// Remove cached builds, keeping only the most recently used ones
+    Prune {
+        /// How many builds to keep
+        #[clap(long, value_parser, default_value_t = 0)]
+        keep: usize,
+    },
 }
 
-const ARG_UPDATE: &str = "update";
-const ARG_BENCH: &str = "bench";
-const ARG_UPDATE_NEXT: &str = "next";
-const ARG_UPDATE_RELEASE: &str = "release";
+#[derive(Debug, Subcommand)]
+enum UpdateTarget {
+    /// Update the preset result for the latest commit on `next`
+    Next,
+    /// Update the preset result for the latest release
+    Release {
+        /// The tag of the release (for example, `v0.7.0`)
+        #[clap(value_parser = parse_ref)]
+        tag: String,
+    },
+}
+
+/// Validates a git ref passed on the command line. We don't attempt to check if the ref
+/// actually exists, but we do reject values that git would misinterpret as flags
+fn parse_ref(input: &str) -> Result<String, String> {
+    if input.is_empty() {
+        return Err("the ref cannot be empty".to_owned());
+    }
+    if input.starts_with('-') {
+        return Err(format!("`{}` is not a valid ref", input));
+    }
+    if input.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(format!("`{}` contains whitespace", input.escape_debug()));
+    }
+    Ok(input.to_owned())
+}
 
 #[derive(Debug, PartialEq)]
 pub struct NewBench {
     commit: String,
-    pull: u16,
+    pull: Option<u16>,
+    interleaved: bool,
 }
 
 impl NewBench {
-    pub const fn new(commit: String, pull: u16) -> Self {
-        Self { commit, pull }
+    pub const fn new(commit: String, pull: Option<u16>, interleaved: bool) -> Self {
+        Self {
+            commit,
+            pull,
+            interleaved,
+        }
+    }
+    pub const fn interleaved(&self) -> bool {
+        self.interleaved
     }
-    pub const fn pull(&self) -> u16 {
+    pub const fn pull(&self) -> Option<u16> {
         self.pull
     }
     pub const fn commit(&self) -> &String {
@@ -53,63 +188,127 @@ impl NewBench {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Compare {
+    base: String,
+    candidate: String,
+    interleaved: bool,
+}
+
+impl Compare {
+    pub const fn new(base: String, candidate: String, interleaved: bool) -> Self {
+        Self {
+            base,
+            candidate,
+            interleaved,
+        }
+    }
+    pub const fn interleaved(&self) -> bool {
+        self.interleaved
+    }
+    pub const fn base(&self) -> &String {
+        &self.base
+    }
+    pub const fn candidate(&self) -> &String {
+        &self.candidate
+    }
+}
+
+#[derive(Debug, PartialEq, clap::Args)]
+pub struct HistoryFilter {
+    /// Only show results for this pull request
+    #[clap(long, value_parser)]
+    pub pr: Option<u16>,
+    /// Only show results for commits starting with this SHA
+    #[clap(long, value_parser = parse_ref)]
+    pub commit: Option<String>,
+    /// Only show results from this date onwards (`YYYY-MM-DD`)
+    #[clap(long, value_parser = Timestamp::parse_date)]
+    pub since: Option<Timestamp>,
+    /// Only show results up to (and including) this date (`YYYY-MM-DD`)
+    #[clap(long, value_parser = Timestamp::parse_date)]
+    pub until: Option<Timestamp>,
+    /// Only show this metric
+    #[clap(long, value_enum)]
+    pub metric: Option<Metric>,
+}
+
+#[derive(Debug, PartialEq, clap::Args)]
+pub struct ValidateOptions {
+    /// Rename files whose names only have stray whitespace
+    #[clap(long)]
+    pub fix: bool,
+    /// Move files that can't be repaired to the `quarantine` directory
+    #[clap(long)]
+    pub quarantine: bool,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Action {
     UpdateNext,
     UpdateRelease(String),
     NewBench(NewBench),
+    Compare(Compare),
+    History(HistoryFilter),
+    Validate(ValidateOptions),
+    Queue(QueueCommand),
+    Cache(CacheCommand),
 }
 
-impl Action {
-    pub fn from_args(args: Vec<String>) -> Self {
-        let mut iter = args.into_iter().skip(1);
-        match iter.next() {
-            Some(arg) => {
-                let action = match arg.as_ref() {
-                    ARG_UPDATE => {
-                        // we need to update
-                        let update_what = nxiter!(iter, "Unknown update action!");
-                        let update = match update_what.as_ref() {
-                            ARG_UPDATE_NEXT => Action::UpdateNext,
-                            ARG_UPDATE_RELEASE => {
-                                // need a release
-                                let tag = nxiter!(iter, "Please provide a tag!");
-                                Action::UpdateRelease(tag)
-                            }
-                            _ => err!("Unknown update action"),
-                        };
-                        update
-                    }
-                    ARG_BENCH => {
-                        // we need to bench
-                        let bench_what_commit = nxiter!(iter, "Please provide the commit to bench");
-                        let bench_which_pr: u16 =
-                            match nxiter!(iter, "Please provider the PR ID").parse() {
-                                Ok(pr) => pr,
-                                _ => err!("Bad value for PR ID"),
-                            };
-                        Action::NewBench(NewBench::new(bench_what_commit, bench_which_pr))
-                    }
-                    _ => err!("Unknown action"),
-                };
-                if iter.next().is_some() {
-                    // hmm, more args? nope, we didn't expect that
-                    err!("Unexpected arguments");
-                }
-                action
-            }
-            None => err!("Please provide a second argument with the action to perform!"),
+impl From<Command> for Action {
+    fn from(command: Command) -> Self {
+        match command {
+            Command::Bench {
+                commit,
+                pull,
+                interleaved,
+            } => Action::NewBench(NewBench::new(commit, pull, interleaved)),
+            Command::Compare {
+                base,
+                candidate,
+                interleaved,
+            } => Action::Compare(Compare::new(base, candidate, interleaved)),
+            Command::Update(UpdateTarget::Next) => Action::UpdateNext,
+            Command::Update(UpdateTarget::Release { tag }) => Action::UpdateRelease(tag),
+            Command::History(filter) => Action::History(filter),
+            Command::Validate(opts) => Action::Validate(opts),
+            Command::Queue(cmd) => Action::Queue(cmd),
+            Command::Cache(cmd) => Action::Cache(cmd),
         }
     }
-    pub fn from_env() -> Self {
-        Self::from_args(env::args().collect())
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub action: Action,
+    pub publish: PublishMode,
+    pub config: Option<PathBuf>,
+}
+
+impl Args {
+    /// Parse the arguments (the first argument is the binary name). Requests for help
+    /// or for the version are also returned as errors; use [`clap::Error::exit`] to print
+    /// them along with the appropriate exit code
+    pub fn from_args<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        Cli::try_parse_from(args).map(|cli| Self {
+            action: cli.command.into(),
+            publish: cli.publish,
+            config: cli.config,
+        })
+    }
+    pub fn from_env() -> Result<Self, clap::Error> {
+        Self::from_args(env::args_os())
     }
 }
 
 #[cfg(test)]
 macro_rules! tvec {
     ($($v:expr),*) => {
-        vec![$($v.into()),*]
+        vec![$($v.to_owned()),*]
     };
 }
 
@@ -117,7 +316,7 @@ macro_rules! tvec {
 fn test_update_release() {
     let args = tvec!["skyreport", "update", "release", "v0.7.0"];
     assert_eq!(
-        Action::from_args(args),
+        Args::from_args(args).unwrap().action,
         Action::UpdateRelease("v0.7.0".to_owned())
     );
 }
@@ -125,14 +324,146 @@ fn test_update_release() {
 #[test]
 fn test_update_next() {
     let args = tvec!["skyreport", "update", "next"];
-    assert_eq!(Action::from_args(args), Action::UpdateNext);
+    assert_eq!(Args::from_args(args).unwrap().action, Action::UpdateNext);
 }
 
 #[test]
 fn test_new_bench() {
     let args = tvec!["skyreport", "bench", "12345abcde", "234"];
     assert_eq!(
-        Action::from_args(args),
-        Action::NewBench(NewBench::new("12345abcde".to_owned(), 234))
+        Args::from_args(args).unwrap().action,
+        Action::NewBench(NewBench::new("12345abcde".to_owned(), Some(234), false))
+    )
+}
+
+#[test]
+fn test_new_bench_without_pr() {
+    let args = tvec!["skyreport", "bench", "v0.7.6"];
+    assert_eq!(
+        Args::from_args(args).unwrap().action,
+        Action::NewBench(NewBench::new("v0.7.6".to_owned(), None, false))
+    );
+    let args = tvec!["skyreport", "bench", "--interleaved", "v0.7.6"];
+    assert_eq!(
+        Args::from_args(args).unwrap().action,
+        Action::NewBench(NewBench::new("v0.7.6".to_owned(), None, true))
+    )
+}
+
+#[test]
+fn test_compare() {
+    let args = tvec!["skyreport", "compare", "next", "my-feature-branch"];
+    assert_eq!(
+        Args::from_args(args).unwrap().action,
+        Action::Compare(Compare::new(
+            "next".to_owned(),
+            "my-feature-branch".to_owned(),
+            false
+        ))
     )
 }
+
+#[test]
+fn test_publish_mode() {
+    let args = tvec!["skyreport", "update", "next"];
+    assert_eq!(Args::from_args(args).unwrap().publish, PublishMode::Github);
+    let args = tvec!["skyreport", "update", "next", "--publish", "dry-run"];
+    assert_eq!(Args::from_args(args).unwrap().publish, PublishMode::DryRun);
+    let args = tvec![
+        "skyreport",
+        "--publish",
+        "local",
+        "bench",
+        "12345abcde",
+        "234"
+    ];
+    assert_eq!(Args::from_args(args).unwrap().publish, PublishMode::Local);
+}
+
+#[test]
+fn test_history() {
+    let args = tvec![
+        "skyreport",
+        "history",
+        "--pr",
+        "222",
+        "--since",
+        "2021-09-11",
+        "--metric",
+        "get"
+    ];
+    assert_eq!(
+        Args::from_args(args).unwrap().action,
+        Action::History(HistoryFilter {
+            pr: Some(222),
+            commit: None,
+            since: Some(Timestamp::parse_date("2021-09-11").unwrap()),
+            until: None,
+            metric: Some(Metric::Get),
+        })
+    );
+    let args = tvec!["skyreport", "history", "--since", "11-09-2021"];
+    assert_eq!(
+        Args::from_args(args).unwrap_err().kind(),
+        clap::ErrorKind::ValueValidation
+    );
+}
+
+#[test]
+fn test_cache() {
+    let args = tvec!["skyreport", "cache", "prune", "--keep", "3"];
+    assert_eq!(
+        Args::from_args(args).unwrap().action,
+        Action::Cache(CacheCommand::Prune { keep: 3 })
+    );
+    let args = tvec!["skyreport", "cache", "list"];
+    assert_eq!(
+        Args::from_args(args).unwrap().action,
+        Action::Cache(CacheCommand::List)
+    );
+}
+
+#[test]
+fn test_bad_pr_id() {
+    use clap::ErrorKind;
+    let args = tvec!["skyreport", "bench", "12345abcde", "notanumber"];
+    assert_eq!(
+        Args::from_args(args).unwrap_err().kind(),
+        ErrorKind::ValueValidation
+    );
+    let args = tvec!["skyreport", "bench", "12345abcde", "65536"];
+    assert_eq!(
+        Args::from_args(args).unwrap_err().kind(),
+        ErrorKind::ValueValidation
+    );
+}
+
+#[test]
+fn test_missing_and_extra_args() {
+    use clap::ErrorKind;
+    let args = tvec!["skyreport", "update", "release"];
+    assert_eq!(
+        Args::from_args(args).unwrap_err().kind(),
+        ErrorKind::MissingRequiredArgument
+    );
+    let args = tvec!["skyreport", "update", "next", "v0.7.0"];
+    assert_eq!(
+        Args::from_args(args).unwrap_err().kind(),
+        ErrorKind::UnknownArgument
+    );
+    let args = tvec!["skyreport", "frobnicate"];
+    assert_eq!(
+        Args::from_args(args).unwrap_err().kind(),
+        ErrorKind::UnknownArgument
+    );
+}
+
+#[test]
+fn test_bad_ref() {
+    use clap::ErrorKind;
+    let args = tvec!["skyreport", "bench", "abc def", "234"];
+    assert_eq!(
+        Args::from_args(args).unwrap_err().kind(),
+        ErrorKind::ValueValidation
+    );
+}
@@ -22,18 +22,28 @@
  *
 */
 
+use crate::affinity::Topology;
+use crate::args::Metric;
+use crate::config::{Config, Workload};
+use crate::environment::Environment;
+use crate::latency::LatencyReport;
+use crate::persistence::Persistence;
+use crate::publisher::Publisher;
+use crate::sampler::ResourceUsage;
+use crate::stats::Spread;
 use crate::updater;
+use crate::updater::ClientInfo;
 use crate::updater::ReportItem;
 use crate::updater::SkyBenchReport;
 use crate::updater::FILE_LATEST_RELEASE;
 use crate::updater::FILE_NEXT;
 use crate::util;
+use crate::watchdog::{self, Stage, StageError, Watchdog};
 use crate::DynResult;
-use octocrab::{models::repos::Object, params::repos::Reference, Octocrab};
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
-use std::io::Write;
+use std::time::Duration;
 
 macro_rules! concat_string {
     ($($e:expr),*) => {{
@@ -43,10 +53,6 @@ macro_rules! concat_string {
     }};
 }
 
-const COMMIT_BASE_URL: &str = "https://github.com/skytable/skytable/commit";
-const PR_BASE_URL: &str = "https://github.com/skytable/skytable/pull";
-const FILE_URL: &str = "https://github.com/skytable/perf/blob/next/reports";
-
 #[derive(Debug, Deserialize, Serialize, Clone)]
 /// A report, which is a structure for JSON of the following form:
 /// ```json
@@ -56,13 +62,34 @@ const FILE_URL: &str = "https://github.com/skytable/perf/blob/next/reports";
 ///     "update": 389244.75
 /// }
 /// ```
+///
+/// If the report was aggregated from multiple runs, then the stats are the mean across
+/// the runs, and the spread of each stat is in `stats`
 pub struct Report {
     get: f32,
     set: f32,
     update: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stats: Option<ReportStats>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+/// The spread of each stat in an aggregated [`Report`]
+pub struct ReportStats {
+    get: Spread,
+    set: Spread,
+    update: Spread,
 }
 
 impl Report {
+    pub const fn new(get: f32, set: f32, update: f32) -> Self {
+        Self {
+            get,
+            set,
+            update,
+            stats: None,
+        }
+    }
     /// This parses the output from `sky-bench` which looks like:
     /// ```json
     /// [{"report":"NAME","stat":1234567.89}]
@@ -76,45 +103,193 @@ impl Report {
             get: from_stdout[0].stat,
             set: from_stdout[1].stat,
             update: from_stdout[2].stat,
+            stats: None,
         })
     }
+    /// Aggregates the reports from multiple runs into a single report
+    pub fn from_runs(runs: &[Report]) -> DynResult<Self> {
+        if runs.is_empty() {
+            return rerr!("No runs to aggregate");
+        }
+        let spread = |metric| Spread::from_samples(runs.iter().map(|r| r.metric(metric)).collect());
+        let stats = ReportStats {
+            get: spread(Metric::Get),
+            set: spread(Metric::Set),
+            update: spread(Metric::Update),
+        };
+        Ok(Self {
+            get: stats.get.mean,
+            set: stats.set.mean,
+            update: stats.update.mean,
+            stats: Some(stats),
+        })
+    }
+    /// Returns the stat for the provided metric
+    pub const fn metric(&self, metric: Metric) -> f32 {
+        match metric {
+            Metric::Get => self.get,
+            Metric::Set => self.set,
+            Metric::Update => self.update,
+        }
+    }
+    /// Returns the percentage change of each stat in `self` relative to `prev`
+    pub fn delta(&self, prev: &Report) -> Self {
+        Self {
+            get: delta(self.get, prev.get),
+            set: delta(self.set, prev.set),
+            update: delta(self.update, prev.update),
+            stats: None,
+        }
+    }
+    /// Returns the markdown list items for this report (one for each operation). If
+    /// the report is aggregated, the spread is also shown
+    pub fn to_list_items(&self) -> Vec<String> {
+        Metric::ALL
+            .iter()
+            .map(|metric| {
+                let spread = self.stats.as_ref().map(|stats| match metric {
+                    Metric::Get => &stats.get,
+                    Metric::Set => &stats.set,
+                    Metric::Update => &stats.update,
+                });
+                match spread {
+                    Some(spread) => format!(
+                        "**{}**: {} ({})",
+                        metric.name(),
+                        self.metric(*metric),
+                        spread.summary()
+                    ),
+                    None => format!("**{}**: {}", metric.name(), self.metric(*metric)),
+                }
+            })
+            .collect()
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 /// A raw report written to `results/*.json`. This is created when a bench operation
 /// is run against a commit
 pub struct RawReport {
-    commit: String,
-    pr: String,
-    raw: Report,
-    results: Vec<Comparison>,
+    pub commit: String,
+    /// The pull request, if the bench was run for one
+    pub pr: Option<String>,
+    pub raw: Report,
+    pub results: Vec<Comparison>,
+    /// The time (in milliseconds) the server took to start accepting connections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_ms: Option<u64>,
+    /// Why the run should not be trusted, if it shouldn't be
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invalid: Option<String>,
+    /// The results of the warm-up runs. These are only kept for diagnostics and are
+    /// not a part of `raw`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warmup: Vec<Report>,
+    /// The results for every cell of the workload matrix, if one was configured
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matrix: Vec<MatrixCell>,
+    /// The CPUs that the server and the client ran on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub topology: Option<Topology>,
+    /// The machine and toolchain that the commit was benched on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,
+    /// The server's resource usage during the bench
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceUsage>,
+    /// The latency distribution of every operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyReport>,
+    /// The client that generated the load
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<ClientInfo>,
+    /// The result of the persistence scenario, if it was run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persistence: Option<Persistence>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// The result for a single cell of the workload matrix
+pub struct MatrixCell {
+    pub workload: Workload,
+    pub report: Report,
+    /// The comparisons against the same cell for each baseline (if the baseline has it)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub results: Vec<Comparison>,
+}
+
+impl MatrixCell {
+    pub const fn new(workload: Workload, report: Report) -> Self {
+        Self {
+            workload,
+            report,
+            results: Vec::new(),
+        }
+    }
+    /// Returns this cell, compared against the cell with the same workload in each of
+    /// the `baselines` (which are `(commit, matrix)` pairs)
+    pub fn compared(&self, baselines: &[(&str, &[MatrixCell])]) -> Self {
+        let results = baselines
+            .iter()
+            .filter_map(|(against, matrix)| {
+                matrix
+                    .iter()
+                    .find(|cell| cell.workload == self.workload)
+                    .map(|cell| {
+                        Comparison::new((*against).to_owned(), self.report.delta(&cell.report))
+                    })
+            })
+            .collect();
+        Self {
+            workload: self.workload,
+            report: self.report.clone(),
+            results,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 /// A comparison for the [`RawReport`]
 pub struct Comparison {
-    against: String,
-    result: Report,
+    pub against: String,
+    pub result: Report,
+    /// The result for `against`, if it was benched in the same session (in which case
+    /// `result` is computed only from the paired runs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<Report>,
 }
 
 impl Comparison {
     /// Create a new [`Comparison`] from the provided `against` and `result`
     pub const fn new(against: String, result: Report) -> Self {
-        Self { against, result }
+        Self {
+            against,
+            result,
+            baseline: None,
+        }
+    }
+    /// Create a new [`Comparison`] against a baseline that was benched in the same
+    /// session
+    pub const fn paired(against: String, result: Report, baseline: Report) -> Self {
+        Self {
+            against,
+            result,
+            baseline: Some(baseline),
+        }
     }
 }
 
-async fn bench_inner(crab: &mut Octocrab, commit: &str, pr: u16) -> DynResult<()> {
-    info!("New bench for commit: `{}` in PR#{}", commit, pr);
-    let repo_current_head = match crab
-        .repos("skytable", "skytable")
-        .get_ref(&Reference::Branch("next".to_owned()))
-        .await?
-        .object
-    {
-        Object::Commit { sha, .. } => sha,
-        _ => return rerr!("Bad return for current SHA"),
-    };
+async fn bench_inner(
+    cfg: &Config,
+    publisher: &dyn Publisher,
+    commit: &str,
+    pr: Option<u16>,
+    interleaved: bool,
+) -> DynResult<()> {
+    match pr {
+        Some(pr) => info!("New bench for commit: `{}` in PR#{}", commit, pr),
+        None => info!("New bench for ref: `{}` (no pull request)", commit),
+    }
 
     // just use the CLI data command; no need for fancy libs
     let date = cmd!("date", "+%d%m%Y-%H%M%S").output()?;
@@ -131,20 +306,26 @@ async fn bench_inner(crab: &mut Octocrab, commit: &str, pr: u16) -> DynResult<()
     let json_filename = format!("./results/result-{}.json", datestr);
     let report_filename = format!("./reports/result-{}.md", datestr);
     // set var for the workflow to add the comment
-    let url_to_report = format!(
-        "{base_url}/result-{date}.md",
-        base_url = FILE_URL,
-        date = datestr
-    );
+    let url_to_report = cfg
+        .perf
+        .file_url(&format!("reports/result-{date}.md", date = datestr));
 
-    // get the base output from sky-bench
-    let result = updater::raw_result(commit)?;
-    let current_report: Report = Report::from_stdout(result.as_bytes())?;
-    let result_bytes = serde_json::to_string_pretty(&result)?;
-
-    // write the raw report
-    let mut raw_report_file = fs::File::create(&json_filename)?;
-    raw_report_file.write_all(result_bytes.as_bytes())?;
+    // get the base output from sky-bench. If interleaved, `next` is benched alongside
+    // the commit and we keep the result along with the paired deltas
+    let (result, paired) = match interleaved {
+        true => {
+            let paired = updater::paired_result(cfg, &cfg.repo.branch, commit)?;
+            let deltas = paired.deltas()?;
+            (paired.candidate, Some((paired.base, deltas)))
+        }
+        false => (updater::raw_result(cfg, commit)?, None),
+    };
+    let current_report: Report = result.report()?;
+    let startup_ms = result.startup.as_millis() as u64;
+    let benched_commit = result.commit.clone();
+    let topology = Topology::current(cfg)?;
+    let resources = result.resources();
+    let latency = result.latency()?;
 
     /*
      now decode the files because we need to render the markdown file.
@@ -155,161 +336,511 @@ async fn bench_inner(crab: &mut Octocrab, commit: &str, pr: u16) -> DynResult<()
     let last_release_report = fs::read_to_string(FILE_LATEST_RELEASE)?;
     let last_release_report: ReportItem = serde_json::from_str(&last_release_report)?;
     let last_head_report = fs::read_to_string(FILE_NEXT)?;
-    let last_head_report: ReportItem = serde_json::from_str(&last_head_report)?;
+    let mut last_head_report: ReportItem = serde_json::from_str(&last_head_report)?;
 
-    if last_head_report.commit != repo_current_head {
+    // the preset for `next` isn't needed if it was benched in this session
+    if paired.is_none() && last_head_report.commit != remote_head(cfg)? {
         // current bench is not on the latest commit
-        info!("Current benched commit is not the same as the ref from `next`. Updating benches for `next` first ...");
-        updater::update_next()?;
-        info!("Finished updating benches for `next`");
-    }
-
-    // compare against next
-    let next_delta_get = delta(current_report.get, last_head_report.report.get);
-    let next_delta_set = delta(current_report.set, last_head_report.report.set);
-    let next_delta_update = delta(current_report.update, last_head_report.report.update);
-    let delta_next = Report {
-        get: next_delta_get,
-        set: next_delta_set,
-        update: next_delta_update,
+        info!(
+            "Current benched commit is not the same as the ref from `{branch}`. Updating benches for `{branch}` first ...",
+            branch = cfg.repo.branch
+        );
+        last_head_report = updater::update_next(cfg, publisher)?;
+        info!("Finished updating benches for `{}`", cfg.repo.branch);
+    }
+
+    // compare against next (using only the paired runs, if interleaved)
+    let (next_commit, delta_next, next_comparison) = match paired {
+        Some((ref base, ref deltas)) => (
+            base.commit.clone(),
+            deltas[0].clone(),
+            Comparison::paired(base.commit.clone(), deltas[0].clone(), base.report()?),
+        ),
+        None => {
+            let delta = current_report.delta(&last_head_report.report);
+            (
+                last_head_report.commit.clone(),
+                delta.clone(),
+                Comparison::new(last_head_report.commit.clone(), delta),
+            )
+        }
     };
 
     // compare against last release
-    let last_release_delta_get = delta(current_report.get, last_release_report.report.get);
-    let last_release_delta_set = delta(current_report.set, last_release_report.report.set);
-    let last_release_delta_update = delta(current_report.update, last_release_report.report.update);
-    let delta_last_release = Report {
-        get: last_release_delta_get,
-        set: last_release_delta_set,
-        update: last_release_delta_update,
-    };
+    let delta_last_release = current_report.delta(&last_release_report.report);
+
+    // compare every cell of the matrix against the same cell in the baselines
+    let matrix: Vec<MatrixCell> = result
+        .matrix()?
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| match paired {
+            Some((_, ref deltas)) => {
+                let mut cell =
+                    cell.compared(&[(&last_release_report.commit, &last_release_report.matrix)]);
+                cell.results
+                    .insert(0, Comparison::new(next_commit.clone(), deltas[i].clone()));
+                cell
+            }
+            None => cell.compared(&[
+                (&last_head_report.commit, &last_head_report.matrix),
+                (&last_release_report.commit, &last_release_report.matrix),
+            ]),
+        })
+        .collect();
 
     // prepare the raw report
     let raw_report = RawReport {
         commit: concat_string!(commit),
-        pr: concat_string!(pr),
+        pr: pr.map(|pr| concat_string!(pr)),
         results: vec![
-            Comparison::new(last_head_report.commit.clone(), delta_next.clone()),
+            next_comparison,
             Comparison::new(
                 last_release_report.commit.clone(),
                 delta_last_release.clone(),
             ),
         ],
         raw: current_report.clone(),
+        startup_ms: Some(startup_ms),
+        invalid: result.invalid.clone(),
+        warmup: result.warmup.clone(),
+        matrix,
+        topology: Some(topology.clone()),
+        environment: Some(result.environment.clone()),
+        resources: resources.clone(),
+        latency: latency.clone(),
+        client: Some(result.client.clone()),
+        persistence: result.persistence.clone(),
     };
 
     // write the raw report
-    util::create_and_write_to_file(
+    publisher.write_file(
         &json_filename,
         serde_json::to_string_pretty(&raw_report)?.as_bytes(),
     )?;
 
-    let list_vs_next_title = format!("v/s next ({commit})", commit = &last_head_report.commit);
+    let list_vs_next_title = format!(
+        "v/s {branch} ({commit})",
+        branch = cfg.repo.branch,
+        commit = &next_commit
+    );
     let list_vs_release_title = format!(
         "v/s release ({release})",
         release = last_release_report.commit
     );
     // write the markdown file
     info!("Writing report ...");
-    let mut md = fs::File::create(report_filename)?;
-    md.write_all("# Skyreport\n".as_bytes())?;
-    md.write_all("## Meta\n".as_bytes())?;
-    let commit_str = format!(
-        "Commit: [{commit}]({base_url}/{commit})",
-        commit = util::get_latest_commit()?,
-        base_url = COMMIT_BASE_URL
-    );
-    let pr_str = format!(
-        "Pull request: [{pr}]({pr_base_url}/{pr})",
-        pr = pr,
-        pr_base_url = PR_BASE_URL
-    );
-    md.write_all(render_list(vec![commit_str, pr_str]).as_bytes())?;
+    let mut md = String::from("# Skyreport\n");
+    md.push_str("## Meta\n");
+    let mut meta = vec![format!(
+        "Commit: [{commit}]({url})",
+        commit = benched_commit,
+        url = cfg.repo.commit_url(&benched_commit)
+    )];
+    if commit != benched_commit {
+        // this was a tag, branch or short SHA
+        meta.push(format!("Ref: `{}`", commit));
+    }
+    match pr {
+        Some(pr) => meta.push(format!(
+            "Pull request: [{pr}]({url})",
+            pr = pr,
+            url = cfg.repo.pull_url(pr)
+        )),
+        None => meta.push("Pull request: none".to_owned()),
+    }
+    if paired.is_some() {
+        meta.push(format!(
+            "Baseline: `{branch}` ([{commit}]({url})), benched in the same session over {rounds} interleaved round(s)",
+            branch = cfg.repo.branch,
+            commit = next_commit,
+            url = cfg.repo.commit_url(&next_commit),
+            rounds = cfg.bench.iterations
+        ));
+    }
+    meta.push(format!("Server startup: {} ms", startup_ms));
+    meta.push(format!("Client: {}", result.client.summary()));
+    meta.push(format!("CPU affinity: {}", topology.summary()));
+    if !result.warmup.is_empty() {
+        meta.push(format!(
+            "Warm-up runs: {} (not included in the results)",
+            result.warmup.len()
+        ));
+    }
+    if let Some(ref e) = result.invalid {
+        meta.push(format!("**Invalid run**: {}", e));
+    }
+    // flag the baselines that were benched somewhere else (or with another toolchain)
+    let mut baselines = vec![("release", &last_release_report)];
+    if paired.is_none() {
+        baselines.insert(0, (cfg.repo.branch.as_str(), &last_head_report));
+    }
+    for (name, baseline) in baselines {
+        let differences = match baseline.environment {
+            Some(ref env) => result.environment.differences(env).join(", "),
+            None => "not recorded".to_owned(),
+        };
+        if !differences.is_empty() {
+            meta.push(format!(
+                "**The environment differs from the `{}` baseline**: {}",
+                name, differences
+            ));
+        }
+        // and the ones that were benched with another client, if that matters
+        let difference = match baseline.client {
+            Some(ref client) => result.client.difference(client),
+            None if result.client.is_fixed() => Some("not recorded".to_owned()),
+            None => None,
+        };
+        if let Some(difference) = difference {
+            meta.push(format!(
+                "**The client differs from the `{}` baseline**: {}",
+                name, difference
+            ));
+        }
+    }
+    md.push_str(&render_list(meta));
+    md.push_str(&render_nested_list(
+        "Environment".to_owned(),
+        result.environment.to_list_items(),
+    ));
+
+    let matrix_columns = [
+        (list_vs_next_title.clone(), next_commit.clone()),
+        (
+            list_vs_release_title.clone(),
+            last_release_report.commit.clone(),
+        ),
+    ];
 
     // prepare the next list
-    let next_get_result_string = format!("**GET**: {}", delta_next.get);
-    let next_set_result_string = format!("**SET**: {}", delta_next.set);
-    let next_update_result_string = format!("**UPDATE**: {}", delta_next.update);
-    let next_list = render_nested_list(
-        list_vs_next_title,
-        vec![
-            next_get_result_string,
-            next_set_result_string,
-            next_update_result_string,
-        ],
-    );
+    let next_list = render_nested_list(list_vs_next_title, delta_next.to_list_items());
 
     // prepare the v/s tag list
-    let release_get_result_string = format!("**GET**: {}", delta_last_release.get);
-    let release_set_result_string = format!("**SET**: {}", delta_last_release.set);
-    let release_update_result_string = format!("**UPDATE**: {}", delta_last_release.update);
-    let release_list = render_nested_list(
-        list_vs_release_title,
-        vec![
-            release_get_result_string,
-            release_set_result_string,
-            release_update_result_string,
-        ],
-    );
+    let release_list =
+        render_nested_list(list_vs_release_title, delta_last_release.to_list_items());
 
     // write summary
-    md.write_all("## Summary\n".as_bytes())?;
-    md.write_all(next_list.as_bytes())?;
-    md.write_all(release_list.as_bytes())?;
+    md.push_str("## Summary\n");
+    md.push_str(&next_list);
+    md.push_str(&release_list);
+
+    // write the per-workload results
+    if !raw_report.matrix.is_empty() {
+        md.push_str("## Workload Matrix\n");
+        md.push_str(&render_matrix(&raw_report.matrix, &matrix_columns));
+    }
+
+    // write the latencies
+    if let Some(ref latency) = latency {
+        let next_latency = match paired {
+            Some((ref base, _)) => base.latency()?,
+            None => last_head_report.latency.clone(),
+        };
+        md.push_str("## Latency\n");
+        md.push_str(&render_latency(
+            latency,
+            &[
+                (matrix_columns[0].0.clone(), next_latency.as_ref()),
+                (
+                    matrix_columns[1].0.clone(),
+                    last_release_report.latency.as_ref(),
+                ),
+            ],
+        ));
+    }
+
+    // write the server's resource usage
+    if let Some(ref resources) = resources {
+        let next_resources = match paired {
+            Some((ref base, _)) => base.resources(),
+            None => last_head_report.resources.clone(),
+        };
+        md.push_str("## Resource Usage\n");
+        md.push_str(&render_metrics(
+            "Resource",
+            resources.metrics(),
+            &[
+                (
+                    matrix_columns[0].0.clone(),
+                    next_resources.map(|r| r.metrics()),
+                ),
+                (
+                    matrix_columns[1].0.clone(),
+                    last_release_report.resources.as_ref().map(|r| r.metrics()),
+                ),
+            ],
+        ));
+    }
+
+    // write the persistence scenario's results
+    if let Some(ref persistence) = result.persistence {
+        let next_persistence = match paired {
+            Some((ref base, _)) => base.persistence.clone(),
+            None => last_head_report.persistence.clone(),
+        };
+        md.push_str("## Persistence\n");
+        md.push_str(&render_list(persistence.to_list_items()));
+        md.push_str(&render_metrics(
+            "Metric",
+            persistence.metrics(),
+            &[
+                (
+                    matrix_columns[0].0.clone(),
+                    next_persistence.map(|p| p.metrics()),
+                ),
+                (
+                    matrix_columns[1].0.clone(),
+                    last_release_report
+                        .persistence
+                        .as_ref()
+                        .map(|p| p.metrics()),
+                ),
+            ],
+        ));
+    }
 
     // write raw result
-    md.write_all("## Raw Result\n".as_bytes())?;
-    let current_get_result_string = format!("**GET**: {}", current_report.get);
-    let current_set_result_string = format!("**SET**: {}", current_report.set);
-    let current_update_result_string = format!("**UPDATE**: {}", current_report.update);
-    let current_list = render_list(vec![
-        current_get_result_string,
-        current_set_result_string,
-        current_update_result_string,
-    ]);
-    md.write_all(current_list.as_bytes())?;
+    md.push_str("## Raw Result\n");
+    let current_list = render_list(current_report.to_list_items());
+    md.push_str(&current_list);
+    if let Some((ref base, _)) = paired {
+        md.push_str("## Baseline Result\n");
+        md.push_str(&render_list(base.report()?.to_list_items()));
+    }
+    publisher.write_file(&report_filename, md.as_bytes())?;
     info!("Finished writing report!");
-    commit!(
-        format!("Added result for skytable/skytable#{} [skip ci]", pr),
-        format!("Triggered by {trigger_commit}", trigger_commit = commit)
-    );
-    info!("Adding comment");
-    crab.issues("skytable", "skytable")
-        .create_comment(
-            pr.into(),
-            format!(
-                "The benchmark has completed. Review [the benchmark here]({url})",
-                url = url_to_report
-            ),
-        )
-        .await?;
-    info!("Added comment");
+    let title = match pr {
+        Some(pr) => format!(
+            "Added result for {org}/{repo}#{pr} [skip ci]",
+            org = cfg.repo.org,
+            repo = cfg.repo.name,
+            pr = pr
+        ),
+        None => format!(
+            "Added result for {org}/{repo}@{commit} [skip ci]",
+            org = cfg.repo.org,
+            repo = cfg.repo.name,
+            commit = commit
+        ),
+    };
+    publisher.commit(&[
+        title,
+        format!("Triggered by {trigger_commit}", trigger_commit = commit),
+    ])?;
+    match pr {
+        Some(pr) => {
+            info!("Adding comment");
+            publisher
+                .comment(
+                    pr,
+                    match result.invalid {
+                        Some(ref e) => format!(
+                            "The benchmark has completed, but the run is invalid ({e}). Review [the benchmark here]({url})",
+                            e = e,
+                            url = url_to_report
+                        ),
+                        None => format!(
+                            "The benchmark has completed. Review [the benchmark here]({url})",
+                            url = url_to_report
+                        ),
+                    },
+                )
+                .await?;
+            info!("Added comment");
+        }
+        None => info!("The report is available at: {}", url_to_report),
+    }
     Ok(())
 }
 
-/// Create a new bench for the provided commit and PR
-pub async fn new(commit: &str, pr: u16) -> DynResult<()> {
-    let mut crab = Octocrab::builder()
-        .personal_token(env::var("GH_TOKEN")?)
-        .build()?;
-    if let Err(e) = bench_inner(&mut crab, commit, pr).await {
-        let buildid = env::var(util::VAR_ACTION_RUN_ID).unwrap();
-        crab.issues(util::ORG_NAME, util::REPO_NAME)
-            .create_comment(
-                pr.into(),
-                format!(
-                    "The benchmark build failed. Please review [the logs here](https://github.com/{org}/{repo}/runs/{run}?check_suite_focus=true)",
-                    org=util::ORG_NAME,
-                    repo=util::REPO_PERF,
-                    run=buildid
-                ),
-            )
-            .await?;
+/// Create a new bench for the provided commit (and PR, if any). If `interleaved` is
+/// set, then the latest commit on the base branch is benched in the same session
+pub async fn new(
+    cfg: &Config,
+    publisher: &dyn Publisher,
+    commit: &str,
+    pr: Option<u16>,
+    interleaved: bool,
+) -> DynResult<()> {
+    if let Err(e) = bench_inner(cfg, publisher, commit, pr, interleaved).await {
+        let pr = match pr {
+            Some(pr) => pr,
+            // nobody to notify
+            None => return Err(e),
+        };
+        let buildid = env::var(util::VAR_ACTION_RUN_ID).unwrap_or_default();
+        let url = cfg.perf.run_url(&buildid);
+        let comment = match e.downcast_ref::<StageError>() {
+            // tell them which stage failed (or timed out)
+            Some(e) => format!(
+                "The benchmark failed because {e}. Please review [the logs here]({url})",
+                e = e,
+                url = url
+            ),
+            None => format!(
+                "The benchmark build failed. Please review [the logs here]({url})",
+                url = url
+            ),
+        };
+        publisher.comment(pr, comment).await?;
         return Err(e);
     }
     Ok(())
 }
 
+/// Returns the latest commit on the configured branch. This counts as a checkout, so it
+/// has the checkout timeout
+fn remote_head(cfg: &Config) -> DynResult<String> {
+    watchdog::in_stage(Stage::Checkout, || {
+        let watchdog = Watchdog::new(Stage::Checkout, Duration::from_secs(cfg.timeouts.checkout));
+        util::get_remote_head(cfg, &cfg.repo.branch, &watchdog)
+    })
+}
+
+/// Bench `base` and `candidate` on this machine and print the comparison to stdout. This
+/// doesn't need a GitHub token and doesn't publish anything. If `interleaved` is set,
+/// the refs are benched alternately and only the paired runs are compared
+pub fn compare(cfg: &Config, base: &str, candidate: &str, interleaved: bool) -> DynResult<()> {
+    info!("Comparing `{}` against `{}`", candidate, base);
+    let (base_result, candidate_result, deltas) = match interleaved {
+        true => {
+            let paired = updater::paired_result(cfg, base, candidate)?;
+            let deltas = paired.deltas()?;
+            (paired.base, paired.candidate, deltas)
+        }
+        false => {
+            let base_result = updater::raw_result(cfg, base)?;
+            let candidate_result = updater::raw_result(cfg, candidate)?;
+            let deltas = candidate_result.deltas(&base_result)?;
+            (base_result, candidate_result, deltas)
+        }
+    };
+    let base_report = base_result.report()?;
+    let base_commit = base_result.commit.clone();
+    let candidate_report = candidate_result.report()?;
+    let candidate_commit = candidate_result.commit.clone();
+    let delta_base = &deltas[0];
+    let matrix: Vec<MatrixCell> = candidate_result
+        .matrix()?
+        .into_iter()
+        .zip(deltas.iter())
+        .map(|(mut cell, delta)| {
+            cell.results = vec![Comparison::new(base_commit.clone(), delta.clone())];
+            cell
+        })
+        .collect();
+    info!("Finished comparing. Printing results ...");
+    let mut out = String::from("# Comparison\n");
+    let mut meta = vec![
+        format!(
+            "Base: `{}` ({}); server startup: {} ms",
+            base,
+            base_commit,
+            base_result.startup.as_millis()
+        ),
+        format!(
+            "Candidate: `{}` ({}); server startup: {} ms",
+            candidate,
+            candidate_commit,
+            candidate_result.startup.as_millis()
+        ),
+    ];
+    if interleaved {
+        meta.push(format!(
+            "Interleaved over {} round(s); the deltas are from the paired runs",
+            cfg.bench.iterations
+        ));
+    }
+    meta.push(format!("Client: {}", candidate_result.client.summary()));
+    meta.push(format!(
+        "CPU affinity: {}",
+        Topology::current(cfg)?.summary()
+    ));
+    if let Some(ref e) = base_result.invalid {
+        meta.push(format!("**Invalid base run**: {}", e));
+    }
+    if let Some(ref e) = candidate_result.invalid {
+        meta.push(format!("**Invalid candidate run**: {}", e));
+    }
+    let differences = candidate_result
+        .environment
+        .differences(&base_result.environment);
+    if !differences.is_empty() {
+        meta.push(format!(
+            "**The environment differs between the base and the candidate**: {}",
+            differences.join(", ")
+        ));
+    }
+    if let Some(difference) = candidate_result.client.difference(&base_result.client) {
+        meta.push(format!(
+            "**The client differs between the base and the candidate**: {}",
+            difference
+        ));
+    }
+    out.push_str(&render_list(meta));
+    out.push_str(&render_nested_list(
+        "Environment".to_owned(),
+        candidate_result.environment.to_list_items(),
+    ));
+    out.push_str("## Summary\n");
+    out.push_str(&render_nested_list(
+        format!("v/s base ({})", base),
+        delta_base.to_list_items(),
+    ));
+    if !matrix.is_empty() {
+        out.push_str("## Workload Matrix\n");
+        out.push_str(&render_matrix(
+            &matrix,
+            &[(format!("v/s base ({})", base), base_commit.clone())],
+        ));
+    }
+    if let Some(latency) = candidate_result.latency()? {
+        out.push_str("## Latency\n");
+        out.push_str(&render_latency(
+            &latency,
+            &[(
+                format!("v/s base ({})", base),
+                base_result.latency()?.as_ref(),
+            )],
+        ));
+    }
+    if let Some(resources) = candidate_result.resources() {
+        out.push_str("## Resource Usage\n");
+        out.push_str(&render_metrics(
+            "Resource",
+            resources.metrics(),
+            &[(
+                format!("v/s base ({})", base),
+                base_result.resources().map(|r| r.metrics()),
+            )],
+        ));
+    }
+    if let Some(ref persistence) = candidate_result.persistence {
+        out.push_str("## Persistence\n");
+        out.push_str(&render_list(persistence.to_list_items()));
+        out.push_str(&render_metrics(
+            "Metric",
+            persistence.metrics(),
+            &[(
+                format!("v/s base ({})", base),
+                base_result.persistence.as_ref().map(|p| p.metrics()),
+            )],
+        ));
+    }
+    out.push_str("## Raw Result\n");
+    out.push_str(&render_nested_list(
+        format!("base ({})", base),
+        base_report.to_list_items(),
+    ));
+    out.push_str(&render_nested_list(
+        format!("candidate ({})", candidate),
+        candidate_report.to_list_items(),
+    ));
+    print!("{}", out);
+    Ok(())
+}
+
 pub fn delta(now: f32, prev: f32) -> f32 {
     ((now - prev) / prev) * 100_f32
 }
@@ -351,3 +882,205 @@ fn render_nested_list(title: String, input: Vec<String>) -> String {
     });
     st
 }
+
+/// Renders the workload matrix as a markdown table, with a row for every operation in
+/// every cell. `columns` has the title and the commit for every baseline that the cells
+/// were compared against. For example:
+/// ```md
+/// | Connections | Queries | Size | Operation | Result | v/s release (v0.7.0) |
+/// | --- | --- | --- | --- | --- | --- |
+/// | 8 | 1000000 | 4 | GET | 123456 | +1.25% |
+/// ```
+fn render_matrix(cells: &[MatrixCell], columns: &[(String, String)]) -> String {
+    let mut st = String::from("| Connections | Queries | Size | Operation | Result |");
+    let mut separator = String::from("| --- | --- | --- | --- | --- |");
+    columns.iter().for_each(|(title, _)| {
+        st.push_str(&format!(" {} |", title));
+        separator.push_str(" --- |");
+    });
+    st.push('\n');
+    st.push_str(&separator);
+    st.push('\n');
+    for cell in cells {
+        for metric in Metric::ALL.iter() {
+            st.push_str(&format!(
+                "| {} | {} | {} | {} | {} |",
+                cell.workload.connections,
+                cell.workload.queries,
+                cell.workload.size,
+                metric.name(),
+                cell.report.metric(*metric)
+            ));
+            columns.iter().for_each(|(_, commit)| {
+                let delta = cell
+                    .results
+                    .iter()
+                    .find(|c| &c.against == commit)
+                    .map(|c| format!("{:+.2}%", c.result.metric(*metric)));
+                st.push_str(&format!(" {} |", delta.as_deref().unwrap_or("n/a")));
+            });
+            st.push('\n');
+        }
+    }
+    st
+}
+
+/// Renders the latencies as a markdown table, with a row for every percentile of every
+/// operation, and the change against every baseline in `columns` (which has the title
+/// and the latencies for every baseline, if they were recorded). A baseline is only
+/// compared if its latencies were measured the same way. A positive change means that
+/// the operation got slower. For example:
+/// ```md
+/// Measured under load (50 connections)
+///
+/// | Operation | Percentile | Latency | v/s release (v0.7.0) |
+/// | --- | --- | --- | --- |
+/// | GET | p50 | 41 µs | -2.38% |
+/// ```
+fn render_latency(latency: &LatencyReport, columns: &[(String, Option<&LatencyReport>)]) -> String {
+    let mut st = format!("Measured {}\n\n", latency.mode);
+    st.push_str("| Operation | Percentile | Latency |");
+    let mut separator = String::from("| --- | --- | --- |");
+    columns.iter().for_each(|(title, _)| {
+        st.push_str(&format!(" {} |", title));
+        separator.push_str(" --- |");
+    });
+    st.push('\n');
+    st.push_str(&separator);
+    st.push('\n');
+    for metric in Metric::ALL.iter() {
+        let percentiles = latency.metric(*metric).percentiles();
+        for (i, (name, value)) in percentiles.iter().enumerate() {
+            st.push_str(&format!("| {} | {} | {} µs |", metric.name(), name, value));
+            columns.iter().for_each(|(_, baseline)| {
+                let delta = baseline
+                    .filter(|b| b.mode == latency.mode)
+                    .map(|b| b.metric(*metric).percentiles()[i].1)
+                    .filter(|prev| *prev != 0)
+                    .map(|prev| format!("{:+.2}%", delta(*value as f32, prev as f32)));
+                st.push_str(&format!(" {} |", delta.as_deref().unwrap_or("n/a")));
+            });
+            st.push('\n');
+        }
+    }
+    st
+}
+
+/// A metric's name, its raw value and its formatted value
+pub type Metrics = Vec<(&'static str, f64, String)>;
+
+/// Renders the metrics (like the server's resource usage) as a markdown table, with the
+/// change against every baseline in `columns` (which has the title and the metrics for
+/// every baseline, if they were recorded). `kind` is the title of the first column. For
+/// example:
+/// ```md
+/// | Resource | Value | v/s release (v0.7.0) |
+/// | --- | --- | --- |
+/// | Peak RSS | 12.5 MiB | +4.17% |
+/// ```
+fn render_metrics(kind: &str, metrics: Metrics, columns: &[(String, Option<Metrics>)]) -> String {
+    let mut st = format!("| {} | Value |", kind);
+    let mut separator = String::from("| --- | --- |");
+    columns.iter().for_each(|(title, _)| {
+        st.push_str(&format!(" {} |", title));
+        separator.push_str(" --- |");
+    });
+    st.push('\n');
+    st.push_str(&separator);
+    st.push('\n');
+    for (name, value, formatted) in metrics {
+        st.push_str(&format!("| {} | {} |", name, formatted));
+        columns.iter().for_each(|(_, baseline)| {
+            let delta = baseline
+                .as_ref()
+                .and_then(|b| b.iter().find(|m| m.0 == name))
+                .map(|m| m.1)
+                .filter(|prev| *prev != 0.0)
+                .map(|prev| format!("{:+.2}%", delta(value as f32, prev as f32)));
+            st.push_str(&format!(" {} |", delta.as_deref().unwrap_or("n/a")));
+        });
+        st.push('\n');
+    }
+    st
+}
+
+#[test]
+fn test_report_from_runs() {
+    let runs: Vec<Report> = [
+        r#"[{"name":"GET","stat":10.0},{"name":"SET","stat":20.0},{"name":"UPDATE","stat":30.0}]"#,
+        r#"[{"name":"GET","stat":14.0},{"name":"SET","stat":20.0},{"name":"UPDATE","stat":30.0}]"#,
+    ]
+    .iter()
+    .map(|stdout| Report::from_stdout(stdout).unwrap())
+    .collect();
+    let report = Report::from_runs(&runs).unwrap();
+    assert_eq!(report.metric(Metric::Get), 12.0);
+    assert_eq!(report.stats.as_ref().unwrap().get.samples, vec![10.0, 14.0]);
+    assert_eq!(
+        report.to_list_items()[1],
+        "**SET**: 20 (median 20.00, min 20.00, max 20.00, σ 0.00, CV 0.00%, n=2)"
+    );
+    assert!(Report::from_runs(&[]).is_err());
+}
+
+#[test]
+fn test_matrix_compared() {
+    let report = |stat: f32| Report {
+        get: stat,
+        set: stat,
+        update: stat,
+        stats: None,
+    };
+    let cell = |connections, stat| {
+        MatrixCell::new(
+            Workload {
+                connections,
+                queries: 1000,
+                size: 4,
+            },
+            report(stat),
+        )
+    };
+    // the baseline doesn't have the 8 connection cell
+    let baseline = vec![cell(50, 100.0)];
+    let cells: Vec<MatrixCell> = [cell(50, 110.0), cell(8, 50.0)]
+        .iter()
+        .map(|c| c.compared(&[("abcd", &baseline)]))
+        .collect();
+    assert_eq!(cells[0].results[0].result.metric(Metric::Get), 10.0);
+    assert!(cells[1].results.is_empty());
+    let table = render_matrix(&cells, &[("v/s next".to_owned(), "abcd".to_owned())]);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 2 + 2 * 3);
+    assert_eq!(lines[2], "| 50 | 1000 | 4 | GET | 110 | +10.00% |");
+    assert_eq!(lines[5], "| 8 | 1000 | 4 | GET | 50 | n/a |");
+}
+
+#[test]
+fn test_render_metrics() {
+    let usage = |peak_rss_kib, write_bytes| ResourceUsage {
+        samples: 4,
+        peak_rss_kib,
+        avg_rss_kib: 1024,
+        cpu_secs: 1.5,
+        peak_threads: 8,
+        peak_fds: 20,
+        read_bytes: 0,
+        write_bytes,
+    };
+    let baseline = usage(10240, 0);
+    let table = render_metrics(
+        "Resource",
+        usage(12800, 1024 * 1024).metrics(),
+        &[
+            ("v/s next".to_owned(), Some(baseline.metrics())),
+            ("v/s release".to_owned(), None),
+        ],
+    );
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines[0], "| Resource | Value | v/s next | v/s release |");
+    assert_eq!(lines[2], "| Peak RSS | 12.5 MiB | +25.00% | n/a |");
+    assert_eq!(lines[4], "| CPU time | 1.50 s | +0.00% | n/a |");
+    // nothing was written by the baseline
+    assert_eq!(lines[8], "| Disk written | 1.0 MiB | n/a | n/a |");
+}
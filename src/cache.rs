@@ -0,0 +1,393 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::args::CacheCommand;
+use crate::config::Config;
+use crate::history;
+use crate::util::{self, FileLock};
+use crate::watchdog::{Stage, Watchdog};
+use crate::DynResult;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The binaries that are built (and cached) for every commit
+pub const BINARIES: [&str; 2] = ["skyd", "sky-bench"];
+/// The metadata for a cache entry, stored alongside the binaries
+const FILE_ENTRY: &str = "entry.json";
+/// Entries that are still being written have this suffix
+const SUFFIX_PARTIAL: &str = ".partial";
+
+/// Used to give every entry that is being written by this process a unique name
+static PARTIAL_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// The metadata for a cached build
+pub struct Entry {
+    pub commit: String,
+    /// Identifies the build configuration (see [`BuildConfig::fingerprint`])
+    pub fingerprint: String,
+    /// The output of `rustc -V`
+    pub toolchain: String,
+    /// When the entry was created (seconds since the UNIX epoch)
+    pub created: u64,
+    /// When the entry was last used (seconds since the UNIX epoch)
+    pub last_used: u64,
+}
+
+/// Everything (other than the commit) that affects the binaries that are built
+pub struct BuildConfig {
+    /// The output of `rustc -vV`
+    pub rustc: String,
+    pub rustflags: Option<String>,
+    pub release_dir: String,
+}
+
+impl BuildConfig {
+    /// Returns the build configuration for the source tree in `srcdir` (so that any
+    /// `rust-toolchain` file is taken into account)
+    pub fn current(cfg: &Config, srcdir: &Path) -> DynResult<Self> {
+        let watchdog = Watchdog::new(Stage::Build, Duration::from_secs(cfg.timeouts.build));
+        let rustc = watchdog.output(cmd!("rustc", "-vV").current_dir(srcdir))?;
+        if !rustc.status.success() {
+            return rerr!(format!(
+                "Failed to get the rustc version: `{}`",
+                String::from_utf8_lossy(&rustc.stderr)
+            ));
+        }
+        Ok(Self {
+            rustc: String::from_utf8_lossy(&rustc.stdout).trim().to_owned(),
+            rustflags: env::var("RUSTFLAGS").ok(),
+            release_dir: cfg.bench.release_dir.clone(),
+        })
+    }
+    /// Returns a short hash of the build configuration (the start of its SHA-256). For
+    /// the same toolchain and build settings, it is the same across runs, processes
+    /// and Rust releases; a different toolchain gets a different key
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        // every field is length-prefixed, so that they can't run into each other
+        let fields = [
+            Some(self.rustc.as_str()),
+            self.rustflags.as_deref(),
+            Some(self.release_dir.as_str()),
+        ];
+        for field in fields.iter() {
+            match field {
+                Some(field) => {
+                    hasher.update((field.len() as u64 + 1).to_le_bytes());
+                    hasher.update(field.as_bytes());
+                }
+                None => hasher.update(0u64.to_le_bytes()),
+            }
+        }
+        hasher.finalize()[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+    /// Returns the first line of the `rustc` version (for example, `rustc 1.56.0 (...)`)
+    pub fn toolchain(&self) -> &str {
+        self.rustc.lines().next().unwrap_or_default()
+    }
+}
+
+/// A directory with the binaries for every commit that was built, keyed by the commit
+/// and the build configuration
+pub struct Cache {
+    root: PathBuf,
+}
+
+impl Cache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+    fn path(&self, commit: &str, fingerprint: &str) -> PathBuf {
+        self.root.join(format!("{}-{}", commit, fingerprint))
+    }
+    /// Returns the directory with the binaries for `commit`, if they are cached. The
+    /// entry is marked as used
+    pub fn lookup(&self, commit: &str, build: &BuildConfig) -> DynResult<Option<PathBuf>> {
+        let path = self.path(commit, &build.fingerprint());
+        let mut entry = match read_entry(&path) {
+            Ok(entry) => entry,
+            Err(_) => return Ok(None),
+        };
+        if !BINARIES.iter().all(|bin| path.join(bin).is_file()) {
+            warn!("The cached build in `{}` is incomplete", path.display());
+            return Ok(None);
+        }
+        entry.last_used = now()?;
+        fs::write(path.join(FILE_ENTRY), serde_json::to_string_pretty(&entry)?)?;
+        Ok(Some(path))
+    }
+    /// Copies the binaries in `from` into the cache. The entry is written to a
+    /// temporary directory of its own first, so a partially written entry is never
+    /// used, and concurrent jobs storing the same entry don't get in each other's way
+    pub fn store(&self, commit: &str, build: &BuildConfig, from: &Path) -> DynResult<()> {
+        let path = self.path(commit, &build.fingerprint());
+        let mut partial = path.clone().into_os_string();
+        partial.push(format!(
+            "-{}-{}{}",
+            process::id(),
+            PARTIAL_ID.fetch_add(1, Ordering::Relaxed),
+            SUFFIX_PARTIAL
+        ));
+        let partial = PathBuf::from(partial);
+        // `prune` removes partial entries, so it has to wait until this one is done
+        let _lock = self.lock()?;
+        fs::create_dir_all(&partial)?;
+        for bin in BINARIES.iter() {
+            fs::copy(from.join(bin), partial.join(bin))?;
+        }
+        let now = now()?;
+        let entry = Entry {
+            commit: commit.to_owned(),
+            fingerprint: build.fingerprint(),
+            toolchain: build.toolchain().to_owned(),
+            created: now,
+            last_used: now,
+        };
+        fs::write(
+            partial.join(FILE_ENTRY),
+            serde_json::to_string_pretty(&entry)?,
+        )?;
+        if self.is_complete(&path) {
+            // another job stored it in the meantime
+            fs::remove_dir_all(&partial)?;
+            return Ok(());
+        }
+        let _ = fs::remove_dir_all(&path);
+        fs::rename(&partial, &path)?;
+        Ok(())
+    }
+    /// Returns true if `path` is an entry with every binary
+    fn is_complete(&self, path: &Path) -> bool {
+        read_entry(path).is_ok() && BINARIES.iter().all(|bin| path.join(bin).is_file())
+    }
+    /// Locks the cache, so that entries aren't replaced or removed by two jobs at once
+    fn lock(&self) -> DynResult<FileLock> {
+        FileLock::acquire(&util::lock_path(&self.root))
+    }
+    /// Returns every valid entry (with its directory), most recently used first
+    pub fn entries(&self) -> DynResult<Vec<(PathBuf, Entry)>> {
+        let mut entries = Vec::new();
+        if !self.root.exists() {
+            return Ok(entries);
+        }
+        for dirent in fs::read_dir(&self.root)? {
+            let path = dirent?.path();
+            match read_entry(&path) {
+                Ok(entry) => entries.push((path, entry)),
+                Err(e) => warn!("Skipping `{}`: {}", path.display(), e),
+            }
+        }
+        entries.sort_by_key(|(_, entry)| Reverse(entry.last_used));
+        Ok(entries)
+    }
+    /// Removes every entry other than the `keep` most recently used ones, along with
+    /// anything that isn't a valid entry. Returns the number of entries removed
+    pub fn prune(&self, keep: usize) -> DynResult<usize> {
+        if !self.root.exists() {
+            return Ok(0);
+        }
+        let _lock = self.lock()?;
+        let keep: Vec<PathBuf> = self
+            .entries()?
+            .into_iter()
+            .take(keep)
+            .map(|(path, _)| path)
+            .collect();
+        let mut removed = 0;
+        for dirent in fs::read_dir(&self.root)? {
+            let path = dirent?.path();
+            if keep.contains(&path) {
+                continue;
+            }
+            info!("Removing `{}`", path.display());
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+fn read_entry(path: &Path) -> DynResult<Entry> {
+    if path.to_string_lossy().ends_with(SUFFIX_PARTIAL) {
+        return rerr!("the entry was not completely written");
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(
+        path.join(FILE_ENTRY),
+    )?)?)
+}
+
+fn now() -> DynResult<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+/// Makes the binaries for `commit` (checked out in `srcdir`) available in the release
+/// directory, returning the directory. The binaries are taken from the cache if they're
+/// there, or are built (and then cached) otherwise
+pub fn build_or_fetch(
+    cfg: &Config,
+    cache: &Cache,
+    srcdir: &Path,
+    commit: &str,
+) -> DynResult<PathBuf> {
+    if !cfg.cache.enabled {
+        return util::build(cfg, srcdir);
+    }
+    let build = BuildConfig::current(cfg, srcdir)?;
+    let bindir = match cache.lookup(commit, &build)? {
+        Some(cached) => {
+            info!("Using the cached build in `{}`", cached.display());
+            let bindir = srcdir.join(&cfg.bench.release_dir);
+            fs::create_dir_all(&bindir)?;
+            for bin in BINARIES.iter() {
+                fs::copy(cached.join(bin), bindir.join(bin))?;
+            }
+            bindir
+        }
+        None => {
+            let bindir = util::build(cfg, srcdir)?;
+            // a failure to cache the build shouldn't fail the run
+            match cache.store(commit, &build, &bindir) {
+                Ok(()) => info!("Cached the build for `{}`", commit),
+                Err(e) => warn!("Failed to cache the build for `{}`: {}", commit, e),
+            }
+            bindir
+        }
+    };
+    Ok(bindir)
+}
+
+/// Run a `cache` command
+pub fn run(cfg: &Config, command: CacheCommand) -> DynResult<()> {
+    let cache = Cache::new(&cfg.cache.dir);
+    match command {
+        CacheCommand::List => {
+            let now = now()?;
+            let header = ["Commit", "Build", "Toolchain", "Size (MiB)", "Last used"];
+            let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+            let mut table = history::render_row(&header);
+            table.push_str(&history::render_row(&vec!["---".to_owned(); header.len()]));
+            for (path, entry) in cache.entries()? {
+                table.push_str(&history::render_row(&[
+                    history::short_ref(&entry.commit).to_owned(),
+                    entry.fingerprint,
+                    entry.toolchain,
+                    format!("{:.1}", util::dir_size(&path)? as f64 / (1024.0 * 1024.0)),
+                    format!("{}h ago", now.saturating_sub(entry.last_used) / 3600),
+                ]));
+            }
+            print!("{}", table);
+        }
+        CacheCommand::Prune { keep } => {
+            let removed = cache.prune(keep)?;
+            info!("Removed {} cached build(s)", removed);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_cache() {
+    let root = env::temp_dir().join(format!("skyreport-cache-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let bins = root.join("bins");
+    fs::create_dir_all(&bins).unwrap();
+    for bin in BINARIES.iter() {
+        fs::write(bins.join(bin), bin).unwrap();
+    }
+    let cache = Cache::new(root.join("cache"));
+    let build = |rustflags: Option<&str>| BuildConfig {
+        rustc: "rustc 1.56.0 (09c42c458 2021-10-18)\nhost: x86_64-unknown-linux-gnu".to_owned(),
+        rustflags: rustflags.map(str::to_owned),
+        release_dir: "target/release".to_owned(),
+    };
+    assert!(cache.lookup("abcd", &build(None)).unwrap().is_none());
+    cache.store("abcd", &build(None), &bins).unwrap();
+    cache.store("efgh", &build(None), &bins).unwrap();
+    let cached = cache.lookup("abcd", &build(None)).unwrap().unwrap();
+    assert_eq!(fs::read_to_string(cached.join("skyd")).unwrap(), "skyd");
+    // a different build configuration is a different entry
+    assert!(cache
+        .lookup("abcd", &build(Some("-C target-cpu=native")))
+        .unwrap()
+        .is_none());
+    let entries = cache.entries().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        entries[0].1.toolchain,
+        "rustc 1.56.0 (09c42c458 2021-10-18)"
+    );
+    // concurrent stores of the same entry don't clobber each other
+    let stores: Vec<_> = (0..4)
+        .map(|_| {
+            let (root, bins) = (root.clone(), bins.clone());
+            std::thread::spawn(move || {
+                let build = BuildConfig {
+                    rustc: "rustc 1.56.0 (09c42c458 2021-10-18)".to_owned(),
+                    rustflags: None,
+                    release_dir: "target/release".to_owned(),
+                };
+                let cache = Cache::new(root.join("cache"));
+                cache
+                    .store("mnop", &build, &bins)
+                    .map_err(|e| e.to_string())
+            })
+        })
+        .collect();
+    for store in stores {
+        store.join().unwrap().unwrap();
+    }
+    assert_eq!(cache.entries().unwrap().len(), 3);
+    // a leftover from an interrupted store is removed too
+    fs::create_dir_all(root.join("cache").join("ijkl-0.partial")).unwrap();
+    assert_eq!(cache.prune(1).unwrap(), 3);
+    assert_eq!(cache.entries().unwrap().len(), 1);
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_fingerprint() {
+    let build = |rustflags: Option<&str>| BuildConfig {
+        rustc: "rustc 1.56.0 (09c42c458 2021-10-18)\nhost: x86_64-unknown-linux-gnu".to_owned(),
+        rustflags: rustflags.map(str::to_owned),
+        release_dir: "target/release".to_owned(),
+    };
+    // the fingerprint is part of the cache's layout, so it must never change
+    assert_eq!(build(None).fingerprint(), "be5068d368974ef6");
+    assert_ne!(build(None).fingerprint(), build(Some("")).fingerprint());
+}
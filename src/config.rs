@@ -0,0 +1,775 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::affinity;
+use crate::DynResult;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fmt::Display;
+use std::fs;
+use std::net::TcpListener;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The default location of the configuration file
+pub const FILE_CONFIG: &str = "skyreport.toml";
+/// The environment variable that can be used to point to a different configuration file
+pub const VAR_CONFIG: &str = "SKYREPORT_CONFIG";
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// The configuration for `skyreport`, loaded from `skyreport.toml`. For example:
+/// ```toml
+/// [repo]
+/// org = "skytable"
+/// name = "skytable"
+/// branch = "next"
+/// mirror = "mirror"
+///
+/// [perf]
+/// org = "skytable"
+/// name = "perf"
+/// branch = "next"
+///
+/// [bench]
+/// connections = 50
+/// queries = 1000000
+/// size = 4
+/// iterations = 1
+/// warmup_runs = 0
+/// warmup_secs = 0
+///
+/// [bench.matrix]
+/// connections = [8, 50]
+/// size = [4, 1024]
+///
+/// [cache]
+/// enabled = true
+/// dir = "cache"
+///
+/// [server]
+/// host = "127.0.0.1"
+/// port = 2003
+/// startup_timeout = 60
+/// shutdown_timeout = 10
+/// heartbeat = false
+///
+/// [affinity]
+/// server = "0-3"
+/// client = "4-7"
+///
+/// [timeouts]
+/// checkout = 900
+/// build = 3600
+/// bench = 900
+///
+/// [persistence]
+/// keys = 1000000
+/// size = 64
+/// ```
+/// Every key is optional and every key can be overridden with an environment variable
+/// of the form `SKYREPORT_<SECTION>_<KEY>` (for example, `SKYREPORT_REPO_BRANCH`)
+pub struct Config {
+    pub repo: RepoConfig,
+    pub perf: PerfConfig,
+    pub bench: BenchConfig,
+    pub cache: CacheConfig,
+    pub server: ServerConfig,
+    pub affinity: AffinityConfig,
+    pub timeouts: TimeoutConfig,
+    pub persistence: PersistenceConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// The repository that is benchmarked
+pub struct RepoConfig {
+    pub org: String,
+    pub name: String,
+    /// The clone URL. If not set, this is derived from the org and name. This can also
+    /// be a local path or a git bundle
+    pub url: Option<String>,
+    /// The branch that PRs are compared against
+    pub branch: String,
+    /// The directory with the local (bare) mirror of the repo
+    pub mirror: String,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// The repository that the results are published to (this repo)
+pub struct PerfConfig {
+    pub org: String,
+    pub name: String,
+    pub branch: String,
+    /// The user that results are pushed as
+    pub user: String,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// The workload used by `sky-bench`
+pub struct BenchConfig {
+    pub connections: usize,
+    pub queries: usize,
+    pub size: usize,
+    /// How many times the benchmark is run for every ref (or the number of rounds, if
+    /// the refs are interleaved)
+    pub iterations: usize,
+    /// How many (unmeasured) runs are done before the measured ones
+    pub warmup_runs: usize,
+    /// The minimum time (in seconds) spent warming up before the measured runs
+    pub warmup_secs: u64,
+    /// The directory (relative to the source tree) with the release binaries
+    pub release_dir: String,
+    /// The directory that every job's workspace (with the source trees and builds) is
+    /// created in. If not set, the system's temporary directory is used
+    pub workspace: Option<String>,
+    /// The client that generates the load
+    pub client: Client,
+    /// The ref that `sky-bench` is built from. If not set, `sky-bench` is built from the
+    /// ref that is benchmarked
+    pub client_ref: Option<String>,
+    /// The number of distinct keys used by the native client. If this is zero, every
+    /// query uses a different key (like `sky-bench`)
+    pub keyspace: usize,
+    /// How many queries (for every operation) the idle probe runs one at a time to
+    /// measure the latencies when the client can't measure them under load (which is
+    /// the case for `sky-bench`). If this is zero, the probe doesn't run, and there are
+    /// no latencies with `sky-bench`
+    pub latency_queries: usize,
+    /// Additional workloads to benchmark
+    pub matrix: MatrixConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+/// The client that generates the load
+pub enum Client {
+    /// `sky-bench`, built from the ref that is benchmarked
+    SkyBench,
+    /// The load generator built into skyreport
+    Native,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// The workload matrix. Every combination of the values is benchmarked, and an empty
+/// list uses the value from `[bench]`
+pub struct MatrixConfig {
+    pub connections: Vec<usize>,
+    pub queries: Vec<usize>,
+    pub size: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+/// A single workload (or matrix cell) for `sky-bench`
+pub struct Workload {
+    pub connections: usize,
+    pub queries: usize,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// How long (in seconds) each stage can take before it is killed. The server startup
+/// is limited by `server.startup_timeout`
+pub struct TimeoutConfig {
+    /// Fetching the repo and checking out the ref
+    pub checkout: u64,
+    pub build: u64,
+    /// A single run of `sky-bench`
+    pub bench: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// The persistence scenario, where the server is loaded with data, snapshotted, stopped
+/// and restarted
+pub struct PersistenceConfig {
+    /// The number of keys loaded into the server. If this is zero, the scenario isn't
+    /// run
+    pub keys: usize,
+    /// The size (in bytes) of every value
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// The CPUs (as CPU lists, like `0-3,6`) that the server and the client are pinned to.
+/// If not set, they can run on any CPU
+pub struct AffinityConfig {
+    pub server: Option<String>,
+    pub client: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// The cache for the `skyd` and `sky-bench` binaries
+pub struct CacheConfig {
+    /// If disabled, the binaries are always built (and never cached)
+    pub enabled: bool,
+    /// The directory with the cached binaries
+    pub dir: String,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+/// Where the server listens, and how we check that it is ready
+pub struct ServerConfig {
+    pub host: String,
+    /// The port that the server listens on. If this is zero (the default), every job
+    /// picks a free port, so that concurrent jobs don't share a server
+    pub port: u16,
+    /// How long (in seconds) the server has to start accepting connections
+    pub startup_timeout: u64,
+    /// How long (in seconds) the server has to exit after a SIGTERM, before it is killed
+    pub shutdown_timeout: u64,
+    /// Whether to send a Skyhash heartbeat, rather than only waiting for the port to
+    /// be open
+    pub heartbeat: bool,
+    /// How often (in milliseconds) the server's resource usage is sampled. If this is
+    /// zero, the resource usage isn't sampled
+    pub sample_interval_ms: u64,
+}
+
+impl Default for RepoConfig {
+    fn default() -> Self {
+        Self {
+            org: "skytable".to_owned(),
+            name: "skytable".to_owned(),
+            url: None,
+            branch: "next".to_owned(),
+            mirror: "mirror".to_owned(),
+        }
+    }
+}
+
+impl Default for PerfConfig {
+    fn default() -> Self {
+        Self {
+            org: "skytable".to_owned(),
+            name: "perf".to_owned(),
+            branch: "next".to_owned(),
+            user: "glydr".to_owned(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_owned(),
+            port: 0,
+            startup_timeout: 60,
+            shutdown_timeout: 10,
+            heartbeat: false,
+            sample_interval_ms: 250,
+        }
+    }
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            checkout: 900,
+            build: 3600,
+            bench: 900,
+        }
+    }
+}
+
+impl Default for PersistenceConfig {
+    fn default() -> Self {
+        Self { keys: 0, size: 64 }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: "cache".to_owned(),
+        }
+    }
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            connections: 50,
+            queries: 1_000_000,
+            size: 4,
+            iterations: 1,
+            warmup_runs: 0,
+            warmup_secs: 0,
+            release_dir: "target/release".to_owned(),
+            workspace: None,
+            client: Client::SkyBench,
+            client_ref: None,
+            keyspace: 0,
+            latency_queries: 10_000,
+            matrix: MatrixConfig::default(),
+        }
+    }
+}
+
+/// Replaces `$field` with the parsed value of the variable `$var` if it is set
+macro_rules! env_override {
+    ($lookup:expr, $field:expr, $var:literal) => {
+        if let Some(value) = $lookup($var) {
+            $field = parse_override($var, &value)?;
+        }
+    };
+    // a comma separated list
+    ($lookup:expr, $field:expr, $var:literal, list) => {
+        if let Some(value) = $lookup($var) {
+            $field = value
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(|v| parse_override($var, v))
+                .collect::<DynResult<_>>()?;
+        }
+    };
+}
+
+fn parse_override<T: FromStr>(var: &str, value: &str) -> DynResult<T>
+where
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|e| format!("Bad value for `{}`: {}", var, e).into())
+}
+
+impl Config {
+    /// Load the configuration. If `path` is not provided, then the file pointed to by
+    /// `SKYREPORT_CONFIG` is used, falling back to `skyreport.toml` (the defaults are
+    /// used if the fallback doesn't exist). Environment overrides are applied last
+    pub fn load(path: Option<&Path>) -> DynResult<Self> {
+        let explicit = path
+            .map(|p| p.to_path_buf())
+            .or_else(|| env::var_os(VAR_CONFIG).map(Into::into));
+        let mut cfg = match explicit {
+            Some(path) => {
+                info!("Loading configuration from `{}`", path.display());
+                Self::from_toml(&fs::read_to_string(&path)?)?
+            }
+            None if Path::new(FILE_CONFIG).exists() => {
+                info!("Loading configuration from `{}`", FILE_CONFIG);
+                Self::from_toml(&fs::read_to_string(FILE_CONFIG)?)?
+            }
+            None => {
+                info!("No configuration file found. Using defaults");
+                Self::default()
+            }
+        };
+        cfg.apply_overrides(|var| env::var(var).ok())?;
+        if cfg.bench.iterations == 0 {
+            return rerr!("`bench.iterations` must be at least 1");
+        }
+        // fail early if the CPUs are bad
+        affinity::Topology::current(&cfg)?;
+        trace!("Using configuration: {:?}", cfg);
+        Ok(cfg)
+    }
+    pub fn from_toml(input: &str) -> DynResult<Self> {
+        Ok(toml::from_str(input)?)
+    }
+    /// Returns the configuration for a single job. If no port was configured, the job
+    /// gets a free port of its own
+    pub fn for_job(&self) -> DynResult<Self> {
+        let mut cfg = self.clone();
+        if cfg.server.port == 0 {
+            let listener = TcpListener::bind((cfg.server.host.as_str(), 0))?;
+            cfg.server.port = listener.local_addr()?.port();
+            info!("Using port {} for the server", cfg.server.port);
+        }
+        Ok(cfg)
+    }
+    /// Apply overrides from `lookup` (which is the environment, outside of tests)
+    pub fn apply_overrides(&mut self, lookup: impl Fn(&str) -> Option<String>) -> DynResult<()> {
+        env_override!(lookup, self.repo.org, "SKYREPORT_REPO_ORG");
+        env_override!(lookup, self.repo.name, "SKYREPORT_REPO_NAME");
+        if let Some(url) = lookup("SKYREPORT_REPO_URL") {
+            self.repo.url = Some(url);
+        }
+        env_override!(lookup, self.repo.branch, "SKYREPORT_REPO_BRANCH");
+        env_override!(lookup, self.repo.mirror, "SKYREPORT_REPO_MIRROR");
+        env_override!(lookup, self.perf.org, "SKYREPORT_PERF_ORG");
+        env_override!(lookup, self.perf.name, "SKYREPORT_PERF_NAME");
+        env_override!(lookup, self.perf.branch, "SKYREPORT_PERF_BRANCH");
+        env_override!(lookup, self.perf.user, "SKYREPORT_PERF_USER");
+        env_override!(
+            lookup,
+            self.bench.connections,
+            "SKYREPORT_BENCH_CONNECTIONS"
+        );
+        env_override!(lookup, self.bench.queries, "SKYREPORT_BENCH_QUERIES");
+        env_override!(lookup, self.bench.size, "SKYREPORT_BENCH_SIZE");
+        env_override!(lookup, self.bench.iterations, "SKYREPORT_BENCH_ITERATIONS");
+        env_override!(
+            lookup,
+            self.bench.warmup_runs,
+            "SKYREPORT_BENCH_WARMUP_RUNS"
+        );
+        env_override!(
+            lookup,
+            self.bench.warmup_secs,
+            "SKYREPORT_BENCH_WARMUP_SECS"
+        );
+        env_override!(
+            lookup,
+            self.bench.release_dir,
+            "SKYREPORT_BENCH_RELEASE_DIR"
+        );
+        env_override!(
+            lookup,
+            self.bench.matrix.connections,
+            "SKYREPORT_BENCH_MATRIX_CONNECTIONS",
+            list
+        );
+        env_override!(
+            lookup,
+            self.bench.matrix.queries,
+            "SKYREPORT_BENCH_MATRIX_QUERIES",
+            list
+        );
+        env_override!(
+            lookup,
+            self.bench.matrix.size,
+            "SKYREPORT_BENCH_MATRIX_SIZE",
+            list
+        );
+        env_override!(lookup, self.bench.client, "SKYREPORT_BENCH_CLIENT");
+        if let Some(client_ref) = lookup("SKYREPORT_BENCH_CLIENT_REF") {
+            self.bench.client_ref = Some(client_ref);
+        }
+        env_override!(lookup, self.bench.keyspace, "SKYREPORT_BENCH_KEYSPACE");
+        env_override!(
+            lookup,
+            self.bench.latency_queries,
+            "SKYREPORT_BENCH_LATENCY_QUERIES"
+        );
+        if let Some(dir) = lookup("SKYREPORT_BENCH_WORKSPACE") {
+            self.bench.workspace = Some(dir);
+        }
+        env_override!(lookup, self.cache.enabled, "SKYREPORT_CACHE_ENABLED");
+        env_override!(lookup, self.cache.dir, "SKYREPORT_CACHE_DIR");
+        env_override!(lookup, self.server.host, "SKYREPORT_SERVER_HOST");
+        env_override!(lookup, self.server.port, "SKYREPORT_SERVER_PORT");
+        env_override!(
+            lookup,
+            self.server.startup_timeout,
+            "SKYREPORT_SERVER_STARTUP_TIMEOUT"
+        );
+        env_override!(
+            lookup,
+            self.server.shutdown_timeout,
+            "SKYREPORT_SERVER_SHUTDOWN_TIMEOUT"
+        );
+        env_override!(lookup, self.server.heartbeat, "SKYREPORT_SERVER_HEARTBEAT");
+        env_override!(
+            lookup,
+            self.server.sample_interval_ms,
+            "SKYREPORT_SERVER_SAMPLE_INTERVAL_MS"
+        );
+        if let Some(cpus) = lookup("SKYREPORT_AFFINITY_SERVER") {
+            self.affinity.server = Some(cpus);
+        }
+        if let Some(cpus) = lookup("SKYREPORT_AFFINITY_CLIENT") {
+            self.affinity.client = Some(cpus);
+        }
+        env_override!(
+            lookup,
+            self.timeouts.checkout,
+            "SKYREPORT_TIMEOUTS_CHECKOUT"
+        );
+        env_override!(lookup, self.timeouts.build, "SKYREPORT_TIMEOUTS_BUILD");
+        env_override!(lookup, self.timeouts.bench, "SKYREPORT_TIMEOUTS_BENCH");
+        env_override!(lookup, self.persistence.keys, "SKYREPORT_PERSISTENCE_KEYS");
+        env_override!(lookup, self.persistence.size, "SKYREPORT_PERSISTENCE_SIZE");
+        Ok(())
+    }
+}
+
+impl RepoConfig {
+    /// Returns the URL used to clone the repo
+    pub fn clone_url(&self) -> String {
+        match self.url {
+            Some(ref url) => url.clone(),
+            None => format!("https://github.com/{}/{}.git", self.org, self.name),
+        }
+    }
+    pub fn commit_url(&self, commit: &str) -> String {
+        format!(
+            "https://github.com/{}/{}/commit/{}",
+            self.org, self.name, commit
+        )
+    }
+    pub fn pull_url(&self, pr: u16) -> String {
+        format!("https://github.com/{}/{}/pull/{}", self.org, self.name, pr)
+    }
+}
+
+impl PerfConfig {
+    /// Returns the URL for the file at `path` on the results branch
+    pub fn file_url(&self, path: &str) -> String {
+        format!(
+            "https://github.com/{}/{}/blob/{}/{}",
+            self.org, self.name, self.branch, path
+        )
+    }
+    pub fn run_url(&self, run: &str) -> String {
+        format!(
+            "https://github.com/{}/{}/runs/{}?check_suite_focus=true",
+            self.org, self.name, run
+        )
+    }
+    /// Returns the URL that the results are pushed to
+    pub fn push_url(&self, token: &str) -> String {
+        format!(
+            "https://{}:{}@github.com/{}/{}.git",
+            self.user, token, self.org, self.name
+        )
+    }
+}
+
+impl BenchConfig {
+    /// Returns the primary workload. This is the one that the headline results (and
+    /// the history) are for
+    pub const fn workload(&self) -> Workload {
+        Workload {
+            connections: self.connections,
+            queries: self.queries,
+            size: self.size,
+        }
+    }
+    /// Returns every workload that should be benchmarked, starting with the primary
+    /// workload
+    pub fn workloads(&self) -> Vec<Workload> {
+        let axis = |values: &Vec<usize>, default| match values.is_empty() {
+            true => vec![default],
+            false => values.clone(),
+        };
+        let mut workloads = vec![self.workload()];
+        for &connections in &axis(&self.matrix.connections, self.connections) {
+            for &queries in &axis(&self.matrix.queries, self.queries) {
+                for &size in &axis(&self.matrix.size, self.size) {
+                    let workload = Workload {
+                        connections,
+                        queries,
+                        size,
+                    };
+                    if !workloads.contains(&workload) {
+                        workloads.push(workload);
+                    }
+                }
+            }
+        }
+        workloads
+    }
+}
+
+impl Workload {
+    /// Returns the arguments for `sky-bench`
+    pub fn args(&self) -> Vec<String> {
+        vec![
+            format!("-c{}", self.connections),
+            format!("-q{}", self.queries),
+            format!("-s{}", self.size),
+            "--json".to_owned(),
+        ]
+    }
+}
+
+impl fmt::Display for Workload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} connections, {} queries, {}B values",
+            self.connections, self.queries, self.size
+        )
+    }
+}
+
+impl Client {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Client::SkyBench => "sky-bench",
+            Client::Native => "native",
+        }
+    }
+}
+
+impl FromStr for Client {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sky-bench" => Ok(Client::SkyBench),
+            "native" => Ok(Client::Native),
+            _ => Err(format!(
+                "unknown client `{}` (expected `sky-bench` or `native`)",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[test]
+fn test_config_defaults() {
+    let cfg = Config::from_toml("").unwrap();
+    assert_eq!(cfg, Config::default());
+    assert_eq!(
+        cfg.repo.clone_url(),
+        "https://github.com/skytable/skytable.git"
+    );
+    assert_eq!(
+        cfg.bench.workload().args(),
+        vec!["-c50", "-q1000000", "-s4", "--json"]
+    );
+    assert_eq!(cfg.bench.workloads(), vec![cfg.bench.workload()]);
+}
+
+#[test]
+fn test_config_partial() {
+    let cfg = Config::from_toml(
+        r#"
+        [repo]
+        org = "myfork"
+        branch = "experimental"
+
+        [bench]
+        connections = 8
+        "#,
+    )
+    .unwrap();
+    assert_eq!(cfg.repo.org, "myfork");
+    assert_eq!(cfg.repo.name, "skytable");
+    assert_eq!(cfg.repo.branch, "experimental");
+    assert_eq!(
+        cfg.repo.clone_url(),
+        "https://github.com/myfork/skytable.git"
+    );
+    assert_eq!(
+        cfg.bench.workload().args(),
+        vec!["-c8", "-q1000000", "-s4", "--json"]
+    );
+}
+
+#[test]
+fn test_config_unknown_key() {
+    assert!(Config::from_toml("[repo]\nbrnch = \"next\"").is_err());
+}
+
+#[test]
+fn test_config_overrides() {
+    let mut cfg = Config::default();
+    cfg.apply_overrides(|var| match var {
+        "SKYREPORT_REPO_URL" => Some("/srv/git/skytable".to_owned()),
+        "SKYREPORT_BENCH_QUERIES" => Some("1000".to_owned()),
+        _ => None,
+    })
+    .unwrap();
+    assert_eq!(cfg.repo.clone_url(), "/srv/git/skytable");
+    assert_eq!(cfg.bench.queries, 1000);
+    assert!(cfg
+        .apply_overrides(|var| match var {
+            "SKYREPORT_BENCH_SIZE" => Some("four".to_owned()),
+            _ => None,
+        })
+        .is_err());
+}
+
+#[test]
+fn test_config_matrix() {
+    let mut cfg = Config::from_toml(
+        r#"
+        [bench]
+        connections = 50
+
+        [bench.matrix]
+        connections = [8, 50]
+        size = [4, 1024]
+        "#,
+    )
+    .unwrap();
+    let cell = |connections, size| Workload {
+        connections,
+        queries: 1_000_000,
+        size,
+    };
+    // the primary workload comes first, and isn't repeated
+    assert_eq!(
+        cfg.bench.workloads(),
+        vec![cell(50, 4), cell(8, 4), cell(8, 1024), cell(50, 1024)]
+    );
+    cfg.apply_overrides(|var| match var {
+        "SKYREPORT_BENCH_MATRIX_SIZE" => Some("4, 64".to_owned()),
+        _ => None,
+    })
+    .unwrap();
+    assert_eq!(cfg.bench.matrix.size, vec![4, 64]);
+}
+
+#[test]
+fn test_config_client() {
+    let mut cfg = Config::from_toml("[bench]\nclient = \"native\"\nkeyspace = 1000").unwrap();
+    assert_eq!(cfg.bench.client, Client::Native);
+    assert_eq!(cfg.bench.keyspace, 1000);
+    assert!(Config::from_toml("[bench]\nclient = \"redis-benchmark\"").is_err());
+    cfg.apply_overrides(|var| match var {
+        "SKYREPORT_BENCH_CLIENT" => Some("sky-bench".to_owned()),
+        _ => None,
+    })
+    .unwrap();
+    assert_eq!(cfg.bench.client, Client::SkyBench);
+    assert_eq!(cfg.bench.client_ref, None);
+    cfg.apply_overrides(|var| match var {
+        "SKYREPORT_BENCH_CLIENT_REF" => Some("v0.7.5".to_owned()),
+        _ => None,
+    })
+    .unwrap();
+    assert_eq!(cfg.bench.client_ref.as_deref(), Some("v0.7.5"));
+}
+
+#[test]
+fn test_config_for_job() {
+    let mut cfg = Config::default();
+    // every job gets a free port
+    let job = cfg.for_job().unwrap();
+    assert_ne!(job.server.port, 0);
+    assert_eq!(job.bench, cfg.bench);
+    // unless one was configured
+    cfg.server.port = 2003;
+    assert_eq!(cfg.for_job().unwrap().server.port, 2003);
+}
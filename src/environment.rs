@@ -0,0 +1,241 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+
+/// The environment variable with the name of the GitHub Actions runner
+const VAR_RUNNER_NAME: &str = "RUNNER_NAME";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+/// The machine and the toolchain that a benchmark ran on. Anything that couldn't be
+/// found out is left out
+pub struct Environment {
+    pub cpu_model: Option<String>,
+    pub cores: Option<usize>,
+    pub memory_kib: Option<u64>,
+    /// The CPU frequency governor (of the first CPU)
+    pub governor: Option<String>,
+    /// The output of `uname -sr`
+    pub kernel: Option<String>,
+    /// The output of `rustc -vV`
+    pub rustc: Option<String>,
+    /// The output of `cargo -vV`
+    pub cargo: Option<String>,
+    pub rustflags: Option<String>,
+    /// The SHA-256 of the source tree's `Cargo.lock`
+    pub cargo_lock: Option<String>,
+    pub hostname: Option<String>,
+    /// The name of the CI runner, if running in CI
+    pub runner: Option<String>,
+    /// The 1, 5 and 15 minute load averages when the benchmark started
+    pub load_average: Option<String>,
+}
+
+/// Run `program` (in `dir`) and return its trimmed stdout, if it succeeded
+fn output(program: &str, args: &[&str], dir: &Path) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        trace!("`{}` failed: {:?}", program, output);
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Returns the SHA-256 of `data`, in hex
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn read_trimmed(path: impl AsRef<Path>) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_owned())
+}
+
+/// Returns the value of the first `key: value` line in `input` with the given key
+pub fn field<'a>(input: &'a str, key: &str) -> Option<&'a str> {
+    input.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        match k.trim() == key {
+            true => Some(v.trim()),
+            false => None,
+        }
+    })
+}
+
+impl Environment {
+    /// Capture the environment for the source tree in `srcdir` (the toolchain is the
+    /// one that would be used to build it)
+    pub fn capture(srcdir: &Path) -> Self {
+        let cpuinfo = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+        let meminfo = fs::read_to_string("/proc/meminfo").unwrap_or_default();
+        let cargo_lock = fs::read(srcdir.join("Cargo.lock"))
+            .ok()
+            .map(|lock| sha256(&lock));
+        Self {
+            cpu_model: field(&cpuinfo, "model name").map(str::to_owned),
+            cores: thread::available_parallelism().ok().map(|n| n.get()),
+            memory_kib: field(&meminfo, "MemTotal")
+                .and_then(|mem| mem.trim_end_matches("kB").trim().parse().ok()),
+            governor: read_trimmed("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor"),
+            kernel: output("uname", &["-sr"], srcdir),
+            rustc: output("rustc", &["-vV"], srcdir),
+            cargo: output("cargo", &["-vV"], srcdir),
+            rustflags: env::var("RUSTFLAGS").ok(),
+            cargo_lock,
+            hostname: read_trimmed("/proc/sys/kernel/hostname"),
+            runner: env::var(VAR_RUNNER_NAME).ok(),
+            load_average: Self::load_average(),
+        }
+    }
+    /// Returns the current 1, 5 and 15 minute load averages
+    pub fn load_average() -> Option<String> {
+        let loadavg = read_trimmed("/proc/loadavg")?;
+        let averages: Vec<&str> = loadavg.split_whitespace().take(3).collect();
+        Some(averages.join(" "))
+    }
+    /// Returns the names of the properties that differ from `other`, leaving out the
+    /// ones that are expected to change between runs (the load average) or along with
+    /// the code (the `Cargo.lock` hash)
+    pub fn differences(&self, other: &Environment) -> Vec<&'static str> {
+        let mut differences = Vec::new();
+        macro_rules! compare {
+            ($($field:ident),*) => {
+                $(if self.$field != other.$field {
+                    differences.push(stringify!($field));
+                })*
+            };
+        }
+        compare!(
+            cpu_model, cores, memory_kib, governor, kernel, rustc, cargo, rustflags, hostname,
+            runner
+        );
+        differences
+    }
+    /// Returns the markdown list items for the environment
+    pub fn to_list_items(&self) -> Vec<String> {
+        let unknown = || "unknown".to_owned();
+        let first_line = |s: &Option<String>| {
+            s.as_deref()
+                .and_then(|s| s.lines().next())
+                .map(str::to_owned)
+                .unwrap_or_else(unknown)
+        };
+        vec![
+            format!(
+                "CPU: {} ({} cores, governor: {})",
+                self.cpu_model.clone().unwrap_or_else(unknown),
+                self.cores.map(|c| c.to_string()).unwrap_or_else(unknown),
+                self.governor.clone().unwrap_or_else(unknown)
+            ),
+            format!(
+                "Memory: {}",
+                self.memory_kib
+                    .map(|kib| format!("{} MiB", kib / 1024))
+                    .unwrap_or_else(unknown)
+            ),
+            format!("Kernel: {}", self.kernel.clone().unwrap_or_else(unknown)),
+            format!(
+                "Toolchain: {}; {}",
+                first_line(&self.rustc),
+                first_line(&self.cargo)
+            ),
+            format!(
+                "RUSTFLAGS: {}",
+                self.rustflags
+                    .as_ref()
+                    .map(|flags| format!("`{}`", flags))
+                    .unwrap_or_else(|| "none".to_owned())
+            ),
+            format!(
+                "Cargo.lock: {}",
+                self.cargo_lock
+                    .as_ref()
+                    .map(|hash| format!("`{}`", &hash[..hash.len().min(12)]))
+                    .unwrap_or_else(unknown)
+            ),
+            format!(
+                "Host: {}{}",
+                self.hostname.clone().unwrap_or_else(unknown),
+                self.runner
+                    .as_ref()
+                    .map(|runner| format!(" (runner: {})", runner))
+                    .unwrap_or_default()
+            ),
+            format!(
+                "Load average at start: {}",
+                self.load_average.clone().unwrap_or_else(unknown)
+            ),
+        ]
+    }
+}
+
+#[test]
+fn test_field() {
+    let cpuinfo = "processor\t: 0\nmodel name\t: AMD EPYC 7B12\nflags\t\t: fpu vme";
+    assert_eq!(field(cpuinfo, "model name"), Some("AMD EPYC 7B12"));
+    assert_eq!(field(cpuinfo, "cpu MHz"), None);
+    let env = Environment {
+        memory_kib: Some(16384000),
+        rustc: Some("rustc 1.56.0 (09c42c458 2021-10-18)\nbinary: rustc".to_owned()),
+        ..Environment::default()
+    };
+    let items = env.to_list_items();
+    assert_eq!(items[1], "Memory: 16000 MiB");
+    assert_eq!(
+        items[3],
+        "Toolchain: rustc 1.56.0 (09c42c458 2021-10-18); unknown"
+    );
+    let upgraded = Environment {
+        rustc: Some("rustc 1.57.0 (f1edd0429 2021-11-29)".to_owned()),
+        load_average: Some("0.10 0.20 0.30".to_owned()),
+        ..env.clone()
+    };
+    assert_eq!(env.differences(&upgraded), vec!["rustc"]);
+}
+
+#[test]
+fn test_cargo_lock_hash() {
+    let root = env::temp_dir().join(format!("skyreport-env-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    assert_eq!(Environment::capture(&root).cargo_lock, None);
+    fs::write(root.join("Cargo.lock"), "abc").unwrap();
+    assert_eq!(
+        Environment::capture(&root).cargo_lock.as_deref(),
+        Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+    );
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,292 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::args::{HistoryFilter, Metric};
+use crate::bencher::RawReport;
+use crate::util;
+use crate::DynResult;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+const FILE_PREFIX: &str = "result-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// The time at which a bench was run. The field order matters, since we derive `Ord`
+pub struct Timestamp {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl Timestamp {
+    fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Option<Self> {
+        let valid = (1..=12).contains(&month)
+            && (1..=31).contains(&day)
+            && hour < 24
+            && minute < 60
+            && second < 60;
+        if valid {
+            Some(Self {
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+            })
+        } else {
+            None
+        }
+    }
+    /// Parses a date of the form `YYYY-MM-DD`, as accepted on the command line
+    pub fn parse_date(input: &str) -> Result<Self, String> {
+        let bad = || format!("`{}` is not a date of the form `YYYY-MM-DD`", input);
+        let parts: Vec<&str> = input.split('-').collect();
+        match parts.as_slice() {
+            [y, m, d] if y.len() == 4 && m.len() == 2 && d.len() == 2 => {
+                let (y, m, d) = (
+                    y.parse().map_err(|_| bad())?,
+                    m.parse().map_err(|_| bad())?,
+                    d.parse().map_err(|_| bad())?,
+                );
+                Self::new(y, m, d, 0, 0, 0).ok_or_else(bad)
+            }
+            _ => Err(bad()),
+        }
+    }
+    /// Parses the timestamp from the name of a result file, which is of the form
+    /// `result-DDMMYYYY-HHMMSS.json` (or `.md` for reports)
+    pub fn from_filename(name: &str) -> Option<Self> {
+        let stem = name
+            .strip_prefix(FILE_PREFIX)?
+            .strip_suffix(".json")
+            .or_else(|| name.strip_prefix(FILE_PREFIX)?.strip_suffix(".md"))?;
+        let (date, time) = (stem.get(..8)?, stem.get(9..)?);
+        if stem.as_bytes()[8] != b'-'
+            || time.len() != 6
+            || !date.bytes().chain(time.bytes()).all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        Self::new(
+            date[4..8].parse().ok()?,
+            date[2..4].parse().ok()?,
+            date[0..2].parse().ok()?,
+            time[0..2].parse().ok()?,
+            time[2..4].parse().ok()?,
+            time[4..6].parse().ok()?,
+        )
+    }
+    /// Returns the last second of the day
+    const fn end_of_day(self) -> Self {
+        Self {
+            hour: 23,
+            minute: 59,
+            second: 59,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[derive(Debug)]
+/// A raw report along with the time at which it was created
+pub struct Entry {
+    pub timestamp: Timestamp,
+    pub report: RawReport,
+}
+
+/// Loads every raw report in `dir`, sorted by time. Files that can't be read or
+/// decoded are skipped (with a warning)
+pub fn load_results(dir: impl AsRef<Path>) -> DynResult<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for file in fs::read_dir(dir)? {
+        let path = file?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let timestamp = match Timestamp::from_filename(&name) {
+            Some(ts) => ts,
+            None => {
+                warn!("Skipping `{}`: bad file name", name.escape_debug());
+                continue;
+            }
+        };
+        let report = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|body| serde_json::from_str(&body).map_err(|e| e.to_string()));
+        match report {
+            Ok(report) => entries.push(Entry { timestamp, report }),
+            Err(e) => warn!("Skipping `{}`: {}", name.escape_debug(), e),
+        }
+    }
+    entries.sort_by_key(|entry| entry.timestamp);
+    Ok(entries)
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let pr_ok = self
+            .pr
+            .is_none_or(|pr| entry.report.pr == Some(pr.to_string()));
+        let commit_ok = self
+            .commit
+            .as_ref()
+            .is_none_or(|commit| entry.report.commit.starts_with(commit.as_str()));
+        let since_ok = self.since.is_none_or(|since| entry.timestamp >= since);
+        let until_ok = self
+            .until
+            .is_none_or(|until| entry.timestamp <= until.end_of_day());
+        pr_ok && commit_ok && since_ok && until_ok
+    }
+    /// Returns the metrics that are to be shown
+    pub fn metrics(&self) -> Vec<Metric> {
+        match self.metric {
+            Some(metric) => vec![metric],
+            None => Metric::ALL.to_vec(),
+        }
+    }
+}
+
+/// Shortens a commit SHA for display; tags and branches are returned as-is
+pub fn short_ref(input: &str) -> &str {
+    if input.len() == 40 && input.bytes().all(|b| b.is_ascii_hexdigit()) {
+        &input[..7]
+    } else {
+        input
+    }
+}
+
+/// Renders a markdown table with a row for each entry. For every metric, we show
+/// the raw stat and the deltas against everything the result was compared to
+pub fn render_table(entries: &[Entry], metrics: &[Metric]) -> String {
+    let mut header = vec!["Date".to_owned(), "Commit".to_owned(), "PR".to_owned()];
+    metrics.iter().for_each(|metric| {
+        header.push(metric.name().to_owned());
+        header.push(format!("{} Δ (%)", metric.name()));
+    });
+    let mut table = render_row(&header);
+    table.push_str(&render_row(&vec!["---".to_owned(); header.len()]));
+    for entry in entries {
+        let report = &entry.report;
+        let mut row = vec![
+            entry.timestamp.to_string(),
+            short_ref(&report.commit).to_owned(),
+            report.pr.clone().unwrap_or_else(|| "-".to_owned()),
+        ];
+        for metric in metrics {
+            row.push(format!("{:.2}", report.raw.metric(*metric)));
+            let deltas: Vec<String> = report
+                .results
+                .iter()
+                .map(|cmp| {
+                    format!(
+                        "{:+.2} v/s {}",
+                        cmp.result.metric(*metric),
+                        short_ref(&cmp.against)
+                    )
+                })
+                .collect();
+            row.push(deltas.join(", "));
+        }
+        table.push_str(&render_row(&row));
+    }
+    table
+}
+
+pub fn render_row(cells: &[String]) -> String {
+    let mut st = "|".to_owned();
+    cells.iter().for_each(|cell| {
+        st.push(' ');
+        st.push_str(cell);
+        st.push_str(" |");
+    });
+    st.push('\n');
+    st
+}
+
+/// Print the stored results that match the provided filter
+pub fn show(filter: &HistoryFilter) -> DynResult<()> {
+    let entries: Vec<Entry> = load_results(util::DIR_RESULTS)?
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .collect();
+    info!("Found {} matching result(s)", entries.len());
+    print!("{}", render_table(&entries, &filter.metrics()));
+    Ok(())
+}
+
+#[test]
+fn test_timestamp_from_filename() {
+    let ts = Timestamp::from_filename("result-03052022-084929.json").unwrap();
+    assert_eq!(ts.to_string(), "2022-05-03 08:49:29");
+    let ts = Timestamp::from_filename("result-03052022-084929.md").unwrap();
+    assert_eq!(ts.to_string(), "2022-05-03 08:49:29");
+    assert!(Timestamp::from_filename("result-11092021-074657\n.json").is_none());
+    assert!(Timestamp::from_filename("result-11092021.json").is_none());
+    assert!(Timestamp::from_filename("result-11132021-074657.json").is_none());
+    assert!(Timestamp::from_filename("next.json").is_none());
+}
+
+#[test]
+fn test_history_filter() {
+    let entry = Entry {
+        timestamp: Timestamp::from_filename("result-11092021-134236.json").unwrap(),
+        report: serde_json::from_str(
+            r#"{
+                "commit": "a6d8936386835781b9e6fa07d1e1a87dfbd73d96",
+                "pr": "222",
+                "raw": {"get": 1.0, "set": 2.0, "update": 3.0},
+                "results": []
+            }"#,
+        )
+        .unwrap(),
+    };
+    let filter =
+        |pr, commit: Option<&str>, since: Option<&str>, until: Option<&str>| HistoryFilter {
+            pr,
+            commit: commit.map(ToOwned::to_owned),
+            since: since.map(|d| Timestamp::parse_date(d).unwrap()),
+            until: until.map(|d| Timestamp::parse_date(d).unwrap()),
+            metric: None,
+        };
+    assert!(filter(None, None, None, None).matches(&entry));
+    assert!(filter(Some(222), Some("a6d89"), None, None).matches(&entry));
+    assert!(!filter(Some(258), None, None, None).matches(&entry));
+    assert!(!filter(None, Some("4d2c7"), None, None).matches(&entry));
+    assert!(filter(None, None, Some("2021-09-11"), Some("2021-09-11")).matches(&entry));
+    assert!(!filter(None, None, Some("2021-09-12"), None).matches(&entry));
+    assert!(!filter(None, None, None, Some("2021-09-10")).matches(&entry));
+}
@@ -0,0 +1,325 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::args::Metric;
+use crate::config::Config;
+use crate::skyhash::{self, Connection};
+use crate::util;
+use crate::watchdog::{Stage, StageError};
+use crate::DynResult;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hdrhistogram::serialization::{Deserializer, Serializer, V2DeflateSerializer};
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long we wait for the server to reply to a single query
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+/// The number of significant digits kept by the histograms
+const SIGFIG: u8 = 3;
+/// The number of keys removed by a single `DEL` after the idle probe
+const DEL_BATCH: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "kebab-case", tag = "kind")]
+/// How the latencies were measured. Latencies measured in different ways can't be
+/// compared
+pub enum LatencyMode {
+    /// Every query of the benchmark was timed, at the workload's concurrency
+    Load { connections: usize },
+    /// The queries were sent one at a time over a single connection, to an otherwise
+    /// idle server. Older reports were all measured this way
+    #[default]
+    IdleProbe,
+}
+
+impl fmt::Display for LatencyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LatencyMode::Load { connections } => {
+                write!(f, "under load ({} connections)", connections)
+            }
+            LatencyMode::IdleProbe => write!(
+                f,
+                "by the idle serial probe (one query at a time, so this is the idle latency and not the latency under load)"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// The latency distribution (in microseconds) of a single operation
+pub struct Latency {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub max: u64,
+    /// The number of queries that were measured
+    pub count: u64,
+    /// The full distribution, as an HDR histogram (in the V2 deflate format, base64
+    /// encoded)
+    pub histogram: String,
+}
+
+impl Latency {
+    fn from_histogram(histogram: &Histogram<u64>) -> DynResult<Self> {
+        let mut serialized = Vec::new();
+        if let Err(e) = V2DeflateSerializer::new().serialize(histogram, &mut serialized) {
+            return rerr!(format!("Failed to serialize histogram: {:?}", e));
+        }
+        Ok(Self {
+            p50: histogram.value_at_quantile(0.5),
+            p90: histogram.value_at_quantile(0.9),
+            p99: histogram.value_at_quantile(0.99),
+            p999: histogram.value_at_quantile(0.999),
+            max: histogram.max(),
+            count: histogram.len(),
+            histogram: BASE64.encode(serialized),
+        })
+    }
+    /// Returns the full distribution
+    pub fn histogram(&self) -> DynResult<Histogram<u64>> {
+        let serialized = BASE64.decode(&self.histogram)?;
+        match Deserializer::new().deserialize(&mut serialized.as_slice()) {
+            Ok(histogram) => Ok(histogram),
+            Err(e) => rerr!(format!("Failed to deserialize histogram: {:?}", e)),
+        }
+    }
+    /// Returns the name and the value of every percentile (and the max)
+    pub const fn percentiles(&self) -> [(&'static str, u64); 5] {
+        [
+            ("p50", self.p50),
+            ("p90", self.p90),
+            ("p99", self.p99),
+            ("p99.9", self.p999),
+            ("max", self.max),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// The latency distribution of every operation
+pub struct LatencyReport {
+    #[serde(default)]
+    pub mode: LatencyMode,
+    get: Latency,
+    set: Latency,
+    update: Latency,
+}
+
+impl LatencyReport {
+    pub fn from_histograms(
+        mode: LatencyMode,
+        get: &Histogram<u64>,
+        set: &Histogram<u64>,
+        update: &Histogram<u64>,
+    ) -> DynResult<Self> {
+        Ok(Self {
+            mode,
+            get: Latency::from_histogram(get)?,
+            set: Latency::from_histogram(set)?,
+            update: Latency::from_histogram(update)?,
+        })
+    }
+    pub const fn metric(&self, metric: Metric) -> &Latency {
+        match metric {
+            Metric::Get => &self.get,
+            Metric::Set => &self.set,
+            Metric::Update => &self.update,
+        }
+    }
+    /// Combine the reports from multiple runs by adding up their histograms. The reports
+    /// have to be measured the same way
+    pub fn combine(reports: &[LatencyReport]) -> DynResult<Option<Self>> {
+        let (first, rest) = match reports.split_first() {
+            Some(split) => split,
+            None => return Ok(None),
+        };
+        if rest.iter().any(|report| report.mode != first.mode) {
+            return rerr!("Can't combine latencies that were measured differently");
+        }
+        let mut histograms = Vec::with_capacity(Metric::ALL.len());
+        for metric in Metric::ALL.iter() {
+            let mut histogram = first.metric(*metric).histogram()?;
+            for report in rest {
+                if let Err(e) = histogram.add(report.metric(*metric).histogram()?) {
+                    return rerr!(format!("Failed to combine histograms: {:?}", e));
+                }
+            }
+            histograms.push(histogram);
+        }
+        Self::from_histograms(first.mode, &histograms[0], &histograms[1], &histograms[2]).map(Some)
+    }
+}
+
+/// Returns a new histogram for latencies in microseconds
+pub fn new_histogram() -> DynResult<Histogram<u64>> {
+    match Histogram::new(SIGFIG) {
+        Ok(histogram) => Ok(histogram),
+        Err(e) => rerr!(format!("Failed to create histogram: {:?}", e)),
+    }
+}
+
+/// Run a single query of the idle probe, returning how long it took. This fails if the
+/// server returns an error, or if the probe ran out of time (`timeout` ending at
+/// `deadline`)
+fn probe_query(
+    con: &mut Connection,
+    query: &[u8],
+    action: &str,
+    deadline: Instant,
+    timeout: Duration,
+) -> DynResult<Duration> {
+    if Instant::now() >= deadline {
+        return Err(StageError::timed_out(Stage::Bench, timeout).into());
+    }
+    let start = Instant::now();
+    let response = con.run(query)?;
+    let elapsed = start.elapsed();
+    if response.is_error() {
+        return rerr!(format!(
+            "The server returned `{}` for {}",
+            String::from_utf8_lossy(&response.data),
+            action
+        ));
+    }
+    Ok(elapsed)
+}
+
+/// The idle probe: run `queries` queries for every operation (`SET`, then `GET` and then
+/// `UPDATE`, on the same keys) one at a time over a single connection, recording the
+/// latency of each, and then remove the keys. This isn't the latency under load (only
+/// the native client measures that), but it works with any client. The whole probe
+/// has the bench timeout. The server should already be running
+pub fn probe_idle(cfg: &Config, queries: usize) -> DynResult<LatencyReport> {
+    info!(
+        "Measuring idle latencies with {} serial queries per operation ...",
+        queries
+    );
+    let timeout = Duration::from_secs(cfg.timeouts.bench);
+    let deadline = Instant::now() + timeout;
+    let mut con = Connection::connect(util::server_addr(&cfg.server)?, QUERY_TIMEOUT)?;
+    // the keys are unique to this run, so that the `SET`s don't hit existing keys
+    let prefix = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+    let keys: Vec<String> = (0..queries)
+        .map(|i| format!("lat{:x}-{}", prefix, i))
+        .collect();
+    let value = "x".repeat(cfg.bench.size);
+    let mut run = |action: &str| -> DynResult<Histogram<u64>> {
+        let mut histogram = new_histogram()?;
+        for key in keys.iter() {
+            let query = match action {
+                "GET" => skyhash::query(&[action, key]),
+                _ => skyhash::query(&[action, key, &value]),
+            };
+            let elapsed = probe_query(&mut con, &query, action, deadline, timeout)?;
+            histogram.saturating_record(elapsed.as_micros() as u64);
+        }
+        Ok(histogram)
+    };
+    let set = run("SET")?;
+    let get = run("GET")?;
+    let update = run("UPDATE")?;
+    // don't leave the keys behind for whatever runs next
+    for batch in keys.chunks(DEL_BATCH) {
+        let mut query = vec!["DEL"];
+        query.extend(batch.iter().map(String::as_str));
+        probe_query(&mut con, &skyhash::query(&query), "DEL", deadline, timeout)?;
+    }
+    LatencyReport::from_histograms(LatencyMode::IdleProbe, &get, &set, &update)
+}
+
+#[test]
+fn test_latency_report() {
+    let histogram = |values: &[u64]| {
+        let mut histogram = new_histogram().unwrap();
+        values.iter().for_each(|v| histogram.record(*v).unwrap());
+        histogram
+    };
+    let fast = histogram(&(1..=100).collect::<Vec<u64>>());
+    let slow = histogram(&[1000; 100]);
+    let load = LatencyMode::Load { connections: 50 };
+    let first = LatencyReport::from_histograms(load, &fast, &fast, &fast).unwrap();
+    assert_eq!(first.get.p50, 50);
+    assert_eq!(first.get.p99, 99);
+    assert_eq!(first.get.max, 100);
+    assert_eq!(first.get.histogram().unwrap(), fast);
+    let second = LatencyReport::from_histograms(load, &slow, &slow, &slow).unwrap();
+    let idle = LatencyReport::from_histograms(LatencyMode::IdleProbe, &slow, &slow, &slow).unwrap();
+    assert!(LatencyReport::combine(&[first.clone(), idle]).is_err());
+    let combined = LatencyReport::combine(&[first, second]).unwrap().unwrap();
+    assert_eq!(combined.mode, load);
+    assert_eq!(combined.set.count, 200);
+    assert_eq!(combined.set.p50, 100);
+    assert_eq!(combined.set.p90, 1000);
+    assert!(LatencyReport::combine(&[]).unwrap().is_none());
+}
+
+#[test]
+fn test_probe_idle() {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut cfg = Config::default();
+    cfg.server.port = listener.local_addr().unwrap().port();
+    // a fake server that replies to every query (which fits in a single read)
+    let server = std::thread::spawn(move || {
+        let (mut con, _) = listener.accept().unwrap();
+        let mut buf = [0u8; 1024];
+        let mut deletes = 0;
+        loop {
+            let read = con.read(&mut buf).unwrap();
+            if read == 0 {
+                break deletes;
+            }
+            if buf[..read].windows(3).any(|w| w == b"DEL") {
+                deletes += 1;
+            }
+            let response: &[u8] = match buf[..read].windows(3).any(|w| w == b"GET") {
+                true => b"*1\n+4\nxxxx\n",
+                false => b"*1\n!1\n0\n",
+            };
+            con.write_all(response).unwrap();
+        }
+    });
+    let report = probe_idle(&cfg, 10).unwrap();
+    // the keys fit in a single batch
+    assert_eq!(server.join().unwrap(), 1);
+    assert_eq!(report.mode, LatencyMode::IdleProbe);
+    for metric in Metric::ALL.iter() {
+        assert_eq!(report.metric(*metric).count, 10);
+    }
+    // the probe gives up once it runs out of time (the connection is accepted anyway)
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    cfg.server.port = listener.local_addr().unwrap().port();
+    cfg.timeouts.bench = 0;
+    let err = probe_idle(&cfg, 10).unwrap_err();
+    assert_eq!(
+        *err.downcast::<StageError>().unwrap(),
+        StageError::timed_out(Stage::Bench, Duration::from_secs(0))
+    );
+}
@@ -0,0 +1,292 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! The native load generator. Unlike `sky-bench`, this doesn't change with the ref
+//! that is benchmarked, so a change to the client can't change the results
+
+use crate::affinity;
+use crate::bencher::Report;
+use crate::config::{Config, Workload};
+use crate::latency::{self, LatencyMode, LatencyReport};
+use crate::skyhash::{self, AsyncConnection};
+use crate::util;
+use crate::watchdog::{Stage, StageError};
+use crate::DynResult;
+use hdrhistogram::Histogram;
+use std::error::Error;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::runtime;
+use tokio::time;
+
+/// Errors from the load generator have to be sent across threads
+type SendResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Returns the key with the provided index, which is at least `size` bytes long
+fn key(index: usize, size: usize) -> String {
+    format!("{:0>width$x}", index, width = size)
+}
+
+/// The result of a single phase: the throughput (in queries per second), the latency
+/// of every query (in microseconds) and the connections (to be reused)
+type PhaseResult = (f32, Histogram<u64>, Vec<AsyncConnection>);
+
+/// Returns a new histogram for latencies in microseconds
+fn new_histogram() -> SendResult<Histogram<u64>> {
+    latency::new_histogram().map_err(|e| e.to_string().into())
+}
+
+/// Run `queries` (split across the connections, round-robin) and return the throughput
+/// and the latencies along with the connections. Every connection has one query in
+/// flight at a time, so the latencies are for the configured concurrency
+async fn phase(
+    action: &str,
+    connections: Vec<AsyncConnection>,
+    queries: Vec<Vec<u8>>,
+) -> SendResult<PhaseResult> {
+    let total = queries.len();
+    let mut batches: Vec<Vec<Vec<u8>>> = vec![Vec::new(); connections.len()];
+    for (i, query) in queries.into_iter().enumerate() {
+        batches[i % connections.len()].push(query);
+    }
+    let mut histograms = Vec::with_capacity(connections.len());
+    for _ in 0..connections.len() {
+        histograms.push(new_histogram()?);
+    }
+    let start = Instant::now();
+    let tasks: Vec<_> = connections
+        .into_iter()
+        .zip(batches)
+        .zip(histograms)
+        .map(|((mut con, batch), mut histogram)| {
+            tokio::spawn(async move {
+                for query in batch {
+                    let sent = Instant::now();
+                    let response = con.run(&query).await?;
+                    histogram.saturating_record(sent.elapsed().as_micros() as u64);
+                    if response.is_error() {
+                        return Err(format!(
+                            "The server returned `{}`",
+                            String::from_utf8_lossy(&response.data)
+                        )
+                        .into());
+                    }
+                }
+                SendResult::Ok((con, histogram))
+            })
+        })
+        .collect();
+    let mut connections = Vec::with_capacity(tasks.len());
+    let mut latencies = new_histogram()?;
+    for task in tasks {
+        match task.await? {
+            Ok((con, histogram)) => {
+                connections.push(con);
+                if let Err(e) = latencies.add(histogram) {
+                    return rerr!(format!("Failed to combine histograms: {:?}", e));
+                }
+            }
+            Err(e) => return rerr!(format!("{} failed: {}", action, e)),
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f32();
+    trace!("{} ran {} queries in {}s", action, total, elapsed);
+    Ok((total as f32 / elapsed, latencies, connections))
+}
+
+/// Flush the table, so that every run starts out empty
+async fn flush(con: &mut AsyncConnection) -> SendResult<()> {
+    let response = con.run(&skyhash::query(&["FLUSHDB"])).await?;
+    if response.is_error() {
+        return rerr!(format!(
+            "FLUSHDB failed: the server returned `{}`",
+            String::from_utf8_lossy(&response.data)
+        ));
+    }
+    Ok(())
+}
+
+/// Open `count` connections (at least one) to the server
+async fn connect(addr: SocketAddr, count: usize) -> SendResult<Vec<AsyncConnection>> {
+    let mut connections = Vec::with_capacity(count);
+    for _ in 0..count.max(1) {
+        connections.push(AsyncConnection::connect(addr).await?);
+    }
+    Ok(connections)
+}
+
+/// The throughput of every operation, and the latency histograms for GET, SET and
+/// UPDATE (in that order)
+type BenchResult = (Report, [Histogram<u64>; 3]);
+
+/// SET every key in the keyspace, and then run the GETs and then the UPDATEs
+async fn bench(addr: SocketAddr, workload: Workload, keyspace: usize) -> SendResult<BenchResult> {
+    let mut admin = AsyncConnection::connect(addr).await?;
+    flush(&mut admin).await?;
+    let connections = connect(addr, workload.connections).await?;
+    // the queries are encoded up front, so that isn't measured
+    let keys: Vec<String> = (0..keyspace).map(|i| key(i, workload.size)).collect();
+    let value = "x".repeat(workload.size);
+    let queries = |action: &str, count: usize| -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|i| {
+                let key = &keys[i % keyspace];
+                match action {
+                    "GET" => skyhash::query(&[action, key]),
+                    _ => skyhash::query(&[action, key, &value]),
+                }
+            })
+            .collect()
+    };
+    let (set, set_latency, connections) =
+        phase("SET", connections, queries("SET", keyspace)).await?;
+    let (get, get_latency, connections) =
+        phase("GET", connections, queries("GET", workload.queries)).await?;
+    let (update, update_latency, _) =
+        phase("UPDATE", connections, queries("UPDATE", workload.queries)).await?;
+    flush(&mut admin).await?;
+    Ok((
+        Report::new(get, set, update),
+        [get_latency, set_latency, update_latency],
+    ))
+}
+
+/// Empty the table and then SET `keys` keys with values of `size` bytes
+async fn load(addr: SocketAddr, connections: usize, keys: usize, size: usize) -> SendResult<()> {
+    let mut admin = AsyncConnection::connect(addr).await?;
+    flush(&mut admin).await?;
+    let connections = connect(addr, connections).await?;
+    let value = "x".repeat(size);
+    let queries = (0..keys)
+        .map(|i| skyhash::query(&["SET", &key(i, size), &value]))
+        .collect();
+    phase("SET", connections, queries).await?;
+    Ok(())
+}
+
+/// Run `task` to completion (or until the bench stage times out). The load generator
+/// gets a runtime of its own (on a thread of its own, since we might already be on a
+/// runtime's thread), with every worker pinned to the client CPUs
+fn block_on<T, F>(cfg: &Config, task: F) -> DynResult<T>
+where
+    T: Send + 'static,
+    F: Future<Output = SendResult<T>> + Send + 'static,
+{
+    let cpus = affinity::client_cpus(cfg)?;
+    let timeout = Duration::from_secs(cfg.timeouts.bench);
+    let task = thread::spawn(move || -> SendResult<T> {
+        let mut builder = runtime::Builder::new_multi_thread();
+        builder.enable_all();
+        if let Some(cpus) = cpus {
+            builder.on_thread_start(move || {
+                if let Err(e) = cpus.pin_current_thread() {
+                    warn!("Failed to pin the client to CPUs `{}`: {}", cpus, e);
+                }
+            });
+        }
+        let rt = builder.build()?;
+        // the timer has to be created on the runtime
+        match rt.block_on(async move { time::timeout(timeout, task).await }) {
+            Ok(result) => result,
+            Err(_) => Err(StageError::timed_out(Stage::Bench, timeout).into()),
+        }
+    });
+    match task.join() {
+        Ok(result) => result.map_err(|e| e as Box<dyn Error>),
+        Err(_) => rerr!("The native client panicked"),
+    }
+}
+
+/// Run the workload with the native load generator, returning the throughput and the
+/// latency distribution (under the workload's concurrency) of every operation. The
+/// server should already be running
+pub fn run(cfg: &Config, workload: &Workload) -> DynResult<(Report, LatencyReport)> {
+    info!(
+        "Beginning benchmark with the native client ({}) ...",
+        workload
+    );
+    let addr = util::server_addr(&cfg.server)?;
+    let keyspace = match cfg.bench.keyspace {
+        0 => workload.queries,
+        keyspace => keyspace,
+    };
+    let (report, [get, set, update]) = block_on(cfg, bench(addr, *workload, keyspace))?;
+    let latency = LatencyReport::from_histograms(
+        LatencyMode::Load {
+            connections: workload.connections,
+        },
+        &get,
+        &set,
+        &update,
+    )?;
+    Ok((report, latency))
+}
+
+/// Replace the server's data with `keys` keys (with values of `size` bytes), using the
+/// configured number of connections. The server should already be running
+pub fn preload(cfg: &Config, keys: usize, size: usize) -> DynResult<()> {
+    info!("Loading {} keys into the server ...", keys);
+    let addr = util::server_addr(&cfg.server)?;
+    block_on(cfg, load(addr, cfg.bench.connections, keys, size))
+}
+
+#[test]
+fn test_key() {
+    assert_eq!(key(255, 4), "00ff");
+    assert_eq!(key(0x12345, 4), "12345");
+}
+
+#[test]
+fn test_native_client() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    let mut cfg = Config::default();
+    cfg.bench.keyspace = 10;
+    let queries = Arc::new(AtomicUsize::new(0));
+    let counter = queries.clone();
+    cfg.server.port = skyhash::fake::start(move |action| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        match action {
+            "GET" => b"*1\n+1\nx\n",
+            _ => b"*1\n!1\n0\n",
+        }
+    });
+    let workload = Workload {
+        connections: 4,
+        queries: 100,
+        size: 1,
+    };
+    let (report, latency) = run(&cfg, &workload).unwrap();
+    // two flushes, SET for every key, and the GETs and UPDATEs
+    assert_eq!(queries.load(Ordering::SeqCst), 2 + 10 + 100 + 100);
+    assert_eq!(latency.mode, LatencyMode::Load { connections: 4 });
+    for metric in crate::args::Metric::ALL.iter() {
+        assert!(report.metric(*metric) > 0.0);
+    }
+    // every query's latency was recorded
+    assert_eq!(latency.metric(crate::args::Metric::Set).count, 10);
+    assert_eq!(latency.metric(crate::args::Metric::Get).count, 100);
+}
@@ -22,8 +22,9 @@
  *
 */
 
+use crate::config::Config;
 use crate::util::DynResult;
-use args::Action;
+use args::{Action, Args, PublishMode};
 use env_logger::Builder;
 use std::env;
 use std::fs;
@@ -32,32 +33,43 @@ use std::fs;
 mod macros;
 #[macro_use]
 extern crate log;
+mod affinity;
 mod args;
 mod bencher;
+mod cache;
+mod config;
+mod environment;
+mod history;
+mod latency;
+mod loadgen;
+mod persistence;
+mod publisher;
+mod queue;
+mod sampler;
+mod server;
+mod skyhash;
+mod stats;
 mod updater;
 mod util;
+mod validate;
+mod watchdog;
 
 fn main() {
     Builder::new()
         .parse_filters(&env::var("SKYREPORT_LOG").unwrap_or_else(|_| "info".to_owned()))
         .init();
-    runtime();
+    let args = Args::from_env().unwrap_or_else(|e| e.exit());
+    runtime(args);
 }
 
 #[tokio::main]
-async fn runtime() {
-    let what_to_do = Action::from_env();
+async fn runtime(args: Args) {
     let ret = async {
-        cmderr!("git", "config", "--global", "pull.rebase", "true");
-        fs::create_dir_all("preset")?;
-        fs::create_dir_all("results")?;
-        fs::create_dir_all("reports")?;
-        match what_to_do {
-            Action::NewBench(bench) => bencher::new(bench.commit(), bench.pull()).await?,
-            Action::UpdateNext => updater::update_next()?,
-            Action::UpdateRelease(release) => updater::update_release(&release)?,
-        }
-        Ok(())
+        let cfg = Config::load(args.config.as_deref())?;
+        fs::create_dir_all(util::DIR_PRESET)?;
+        fs::create_dir_all(util::DIR_RESULTS)?;
+        fs::create_dir_all(util::DIR_REPORTS)?;
+        execute(&cfg, args.publish, args.action).await
     };
     let errored;
     let ret: DynResult<()> = ret.await;
@@ -71,3 +83,32 @@ async fn runtime() {
         err!("skyreport operation failed");
     }
 }
+
+/// Run the provided action
+async fn execute(cfg: &Config, publish_mode: PublishMode, action: Action) -> DynResult<()> {
+    let publisher = || publisher::new(cfg, publish_mode);
+    match action {
+        Action::NewBench(bench) => {
+            bencher::new(
+                cfg,
+                &*publisher()?,
+                bench.commit(),
+                bench.pull(),
+                bench.interleaved(),
+            )
+            .await?
+        }
+        Action::UpdateNext => {
+            updater::update_next(cfg, &*publisher()?)?;
+        }
+        Action::UpdateRelease(release) => updater::update_release(cfg, &*publisher()?, &release)?,
+        Action::Compare(cmp) => {
+            bencher::compare(cfg, cmp.base(), cmp.candidate(), cmp.interleaved())?
+        }
+        Action::History(filter) => history::show(&filter)?,
+        Action::Validate(opts) => validate::run(&opts)?,
+        Action::Queue(cmd) => queue::run(cfg, publish_mode, cmd).await?,
+        Action::Cache(cmd) => cache::run(cfg, cmd)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,285 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::bencher::Metrics;
+use crate::config::Config;
+use crate::loadgen;
+use crate::skyhash::{self, Connection};
+use crate::util;
+use crate::watchdog::{self, Stage};
+use crate::DynResult;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The name of the snapshot that is created
+const SNAPSHOT_NAME: &str = "skyreport";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// The result of the persistence scenario
+pub struct Persistence {
+    /// The number of keys that were loaded
+    pub keys: usize,
+    /// The size (in bytes) of every value
+    pub size: usize,
+    /// The time (in milliseconds) the server took to create a snapshot, if it could
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_ms: Option<u64>,
+    /// The time (in milliseconds) the server took to flush its data and exit after a
+    /// SIGTERM
+    pub shutdown_ms: u64,
+    /// The size (in bytes) of the data directory after the server exited (including
+    /// the snapshot)
+    pub disk_bytes: u64,
+    /// The time (in milliseconds) the restarted server took to load the data and start
+    /// accepting connections
+    pub recovery_ms: u64,
+    /// The number of keys the restarted server had, if it could be found out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovered_keys: Option<u64>,
+    /// Why the result should not be trusted (for example, because keys were lost)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invalid: Option<String>,
+}
+
+impl Persistence {
+    /// Returns the name, the raw value and the formatted value for every metric
+    pub fn metrics(&self) -> Metrics {
+        let mut metrics = Vec::new();
+        if let Some(snapshot_ms) = self.snapshot_ms {
+            metrics.push((
+                "Snapshot time",
+                snapshot_ms as f64,
+                format!("{} ms", snapshot_ms),
+            ));
+        }
+        metrics.push((
+            "Shutdown time",
+            self.shutdown_ms as f64,
+            format!("{} ms", self.shutdown_ms),
+        ));
+        metrics.push((
+            "Data on disk",
+            self.disk_bytes as f64,
+            format!("{:.1} MiB", self.disk_bytes as f64 / (1024.0 * 1024.0)),
+        ));
+        metrics.push((
+            "Recovery time",
+            self.recovery_ms as f64,
+            format!("{} ms", self.recovery_ms),
+        ));
+        metrics
+    }
+    /// Returns the markdown list items describing the scenario (and any problems)
+    pub fn to_list_items(&self) -> Vec<String> {
+        let mut items = vec![format!(
+            "Loaded {} keys with {}B values",
+            self.keys, self.size
+        )];
+        match self.recovered_keys {
+            Some(recovered) => items.push(format!("Keys after the restart: {}", recovered)),
+            None => items.push("Keys after the restart: unknown".to_owned()),
+        }
+        if self.snapshot_ms.is_none() {
+            items.push("The server couldn't create a snapshot".to_owned());
+        }
+        if let Some(ref e) = self.invalid {
+            items.push(format!("**Invalid run**: {}", e));
+        }
+        items
+    }
+}
+
+/// Run a query that should return a response code, returning the time it took or the
+/// code if it wasn't `0` (okay)
+fn timed_query(con: &mut Connection, query: &[u8]) -> DynResult<Result<Duration, String>> {
+    let start = Instant::now();
+    let response = con.run(query)?;
+    let elapsed = start.elapsed();
+    Ok(match response.is_error() {
+        true => Err(String::from_utf8_lossy(&response.data).into_owned()),
+        false => Ok(elapsed),
+    })
+}
+
+/// Returns the number of keys in the server, if it replies with a number
+fn count_keys(con: &mut Connection) -> DynResult<Option<u64>> {
+    let response = con.run(&skyhash::query(&["DBSIZE"]))?;
+    Ok(match response.tsymbol {
+        b':' => String::from_utf8_lossy(&response.data).parse().ok(),
+        _ => None,
+    })
+}
+
+/// Load the configured number of keys into a fresh server (from `bindir`) running in
+/// `datadir`, snapshot it, stop it, measure the data it left behind and then restart it
+/// and wait for it to recover. The server logs go in `basedir`
+pub fn run(
+    cfg: &Config,
+    basedir: &Path,
+    branch: &str,
+    bindir: &Path,
+    datadir: &Path,
+) -> DynResult<Persistence> {
+    info!(
+        "Running the persistence scenario with {} keys ...",
+        cfg.persistence.keys
+    );
+    let timeout = Duration::from_secs(cfg.timeouts.bench);
+    fs::create_dir_all(datadir)?;
+    let log = util::server_log_path(basedir, &format!("{}-persistence", branch))?;
+    let (server, _) = watchdog::in_stage(Stage::Startup, || {
+        util::start_server_in_background(cfg, bindir, datadir, &log)
+    })?;
+    let snapshot = watchdog::in_stage(Stage::Bench, || {
+        loadgen::preload(cfg, cfg.persistence.keys, cfg.persistence.size)?;
+        let mut con = Connection::connect(util::server_addr(&cfg.server)?, timeout)?;
+        timed_query(&mut con, &skyhash::query(&["MKSNAP", SNAPSHOT_NAME]))
+    })?;
+    let snapshot_ms = match snapshot {
+        Ok(elapsed) => Some(elapsed.as_millis() as u64),
+        Err(code) => {
+            warn!(
+                "The server couldn't create a snapshot (it returned `{}`)",
+                code
+            );
+            None
+        }
+    };
+    // the server flushes its data when it is stopped
+    info!("Stopping server ...");
+    let start = Instant::now();
+    let mut invalid = server.stop()?;
+    let shutdown_ms = start.elapsed().as_millis() as u64;
+    let disk_bytes = util::dir_size(datadir)?;
+    // now restart it, and see how long it takes to load the data
+    info!("Restarting server ...");
+    let (server, recovery) = watchdog::in_stage(Stage::Startup, || {
+        util::start_server_in_background(cfg, bindir, datadir, &log.with_extension("restart.log"))
+    })?;
+    let recovered_keys = watchdog::in_stage(Stage::Bench, || {
+        count_keys(&mut Connection::connect(
+            util::server_addr(&cfg.server)?,
+            timeout,
+        )?)
+    })?;
+    invalid = invalid.or(server.stop()?);
+    if let Some(recovered) = recovered_keys {
+        if recovered < cfg.persistence.keys as u64 {
+            invalid = invalid.or_else(|| {
+                Some(format!(
+                    "only {} of {} keys were recovered",
+                    recovered, cfg.persistence.keys
+                ))
+            });
+        }
+    }
+    Ok(Persistence {
+        keys: cfg.persistence.keys,
+        size: cfg.persistence.size,
+        snapshot_ms,
+        shutdown_ms,
+        disk_bytes,
+        recovery_ms: recovery.as_millis() as u64,
+        recovered_keys,
+        invalid,
+    })
+}
+
+#[test]
+fn test_metrics() {
+    let mut persistence = Persistence {
+        keys: 1000,
+        size: 64,
+        snapshot_ms: None,
+        shutdown_ms: 20,
+        disk_bytes: 3 * 1024 * 1024,
+        recovery_ms: 150,
+        recovered_keys: Some(1000),
+        invalid: None,
+    };
+    let names = |p: &Persistence| -> Vec<&str> { p.metrics().iter().map(|m| m.0).collect() };
+    assert_eq!(
+        names(&persistence),
+        vec!["Shutdown time", "Data on disk", "Recovery time"]
+    );
+    assert_eq!(persistence.metrics()[1].2, "3.0 MiB");
+    assert_eq!(
+        persistence.to_list_items()[2],
+        "The server couldn't create a snapshot"
+    );
+    persistence.snapshot_ms = Some(40);
+    assert_eq!(names(&persistence)[0], "Snapshot time");
+    assert_eq!(persistence.to_list_items().len(), 2);
+}
+
+#[test]
+fn test_run() {
+    use std::env;
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    let root = env::temp_dir().join(format!("skyreport-persistence-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let (bindir, datadir) = (root.join("bin"), root.join("data"));
+    fs::create_dir_all(&bindir).unwrap();
+    // a fake server process that only writes its data when it is stopped; the queries
+    // go to the fake server below
+    let skyd = bindir.join("skyd");
+    fs::write(
+        &skyd,
+        "#!/bin/sh\nsleep 30 &\ntrap 'printf 0123456789 > data.bin; kill $!; exit 0' TERM\nwait\n",
+    )
+    .unwrap();
+    fs::set_permissions(&skyd, fs::Permissions::from_mode(0o755)).unwrap();
+    let mut cfg = Config::default();
+    cfg.server.sample_interval_ms = 0;
+    cfg.persistence.keys = 10;
+    cfg.persistence.size = 4;
+    let sets = Arc::new(AtomicUsize::new(0));
+    let counter = sets.clone();
+    // it loses half of the keys, and can't create snapshots
+    cfg.server.port = skyhash::fake::start(move |action| match action {
+        "DBSIZE" => b"*1\n:1\n5\n",
+        "MKSNAP" => b"*1\n!1\n5\n",
+        "SET" => {
+            counter.fetch_add(1, Ordering::SeqCst);
+            b"*1\n!1\n0\n"
+        }
+        _ => b"*1\n!1\n0\n",
+    });
+    let persistence = run(&cfg, &root, "next", &bindir, &datadir).unwrap();
+    assert_eq!(sets.load(Ordering::SeqCst), 10);
+    assert_eq!(persistence.keys, 10);
+    assert_eq!(persistence.snapshot_ms, None);
+    // the data was flushed on shutdown
+    assert_eq!(persistence.disk_bytes, 10);
+    assert_eq!(persistence.recovered_keys, Some(5));
+    assert_eq!(
+        persistence.invalid.as_deref(),
+        Some("only 5 of 10 keys were recovered")
+    );
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,168 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::args::PublishMode;
+use crate::config::Config;
+use crate::util;
+use crate::DynResult;
+use async_trait::async_trait;
+use octocrab::Octocrab;
+use std::env;
+use std::process::Command;
+
+const VAR_GH_TOKEN: &str = "GH_TOKEN";
+
+#[async_trait(?Send)]
+/// A publisher decides what happens with the results of a run: it writes the result
+/// files, records them and notifies the pull request
+pub trait Publisher {
+    /// Write `body` to the file at `path` (relative to the root of this repo)
+    fn write_file(&self, path: &str, body: &[u8]) -> DynResult<()>;
+    /// Record all the files written so far. Each of the `messages` is a paragraph of
+    /// the commit message
+    fn commit(&self, messages: &[String]) -> DynResult<()>;
+    /// Add a comment with the provided `body` to the pull request `pr`
+    async fn comment(&self, pr: u16, body: String) -> DynResult<()>;
+}
+
+/// Returns the publisher for the provided mode
+pub fn new<'a>(cfg: &'a Config, mode: PublishMode) -> DynResult<Box<dyn Publisher + 'a>> {
+    let publisher: Box<dyn Publisher> = match mode {
+        PublishMode::Github => Box::new(GithubPublisher::new(cfg)?),
+        PublishMode::Local => Box::new(LocalPublisher),
+        PublishMode::DryRun => Box::new(DryRunPublisher),
+    };
+    Ok(publisher)
+}
+
+/// Writes files locally, pushes them to the perf repo and comments on GitHub
+pub struct GithubPublisher<'a> {
+    cfg: &'a Config,
+    token: String,
+    crab: Octocrab,
+}
+
+impl<'a> GithubPublisher<'a> {
+    pub fn new(cfg: &'a Config) -> DynResult<Self> {
+        let token = env::var(VAR_GH_TOKEN)?;
+        let crab = Octocrab::builder().personal_token(token.clone()).build()?;
+        run_git(
+            &mut cmd!("git", "config", "--global", "pull.rebase", "true"),
+            "Failed to configure git",
+        )?;
+        Ok(Self { cfg, token, crab })
+    }
+}
+
+/// Run a git command, returning an error with `failure` if it fails
+fn run_git(cmd: &mut Command, failure: &str) -> DynResult<()> {
+    let output = cmd.output()?;
+    if !output.status.success() {
+        error!(
+            "Child failed with: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return rerr!(failure);
+    }
+    Ok(())
+}
+
+#[async_trait(?Send)]
+impl<'a> Publisher for GithubPublisher<'a> {
+    fn write_file(&self, path: &str, body: &[u8]) -> DynResult<()> {
+        util::create_and_write_to_file(path, body)
+    }
+    fn commit(&self, messages: &[String]) -> DynResult<()> {
+        trace!("Adding files ...");
+        run_git(&mut cmd!("git", "add", "."), "Failed to add results")?;
+        trace!("Committing files ...");
+        let mut cmd = cmd!("git", "commit");
+        messages.iter().for_each(|msg| {
+            cmd.arg("-m").arg(msg);
+        });
+        run_git(&mut cmd, "Failed to commit results")?;
+        trace!("Pulling latest ... ");
+        run_git(
+            &mut cmd!("git", "pull", "--rebase"),
+            "Failed to pull the latest results",
+        )?;
+        trace!("Publishing results ...");
+        run_git(
+            &mut cmd!("git", "push", self.cfg.perf.push_url(&self.token), "--all"),
+            "Failed to push results",
+        )
+    }
+    async fn comment(&self, pr: u16, body: String) -> DynResult<()> {
+        self.crab
+            .issues(&self.cfg.repo.org, &self.cfg.repo.name)
+            .create_comment(pr.into(), body)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Only writes files to the local directory; nothing is committed and no comments
+/// are added
+pub struct LocalPublisher;
+
+#[async_trait(?Send)]
+impl Publisher for LocalPublisher {
+    fn write_file(&self, path: &str, body: &[u8]) -> DynResult<()> {
+        util::create_and_write_to_file(path, body)
+    }
+    fn commit(&self, messages: &[String]) -> DynResult<()> {
+        info!("Keeping results local. Not committing: {:?}", messages);
+        Ok(())
+    }
+    async fn comment(&self, pr: u16, body: String) -> DynResult<()> {
+        info!(
+            "Keeping results local. Not commenting on PR#{}: {}",
+            pr, body
+        );
+        Ok(())
+    }
+}
+
+/// Doesn't write anything at all; only logs what would have been done
+pub struct DryRunPublisher;
+
+#[async_trait(?Send)]
+impl Publisher for DryRunPublisher {
+    fn write_file(&self, path: &str, body: &[u8]) -> DynResult<()> {
+        info!("[dry run] Would write {} bytes to `{}`", body.len(), path);
+        trace!("[dry run] Contents: {}", String::from_utf8_lossy(body));
+        Ok(())
+    }
+    fn commit(&self, messages: &[String]) -> DynResult<()> {
+        info!(
+            "[dry run] Would commit and push with message: {:?}",
+            messages
+        );
+        Ok(())
+    }
+    async fn comment(&self, pr: u16, body: String) -> DynResult<()> {
+        info!("[dry run] Would comment on PR#{}: {}", pr, body);
+        Ok(())
+    }
+}
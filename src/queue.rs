@@ -0,0 +1,276 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::args::{Action, Args, PublishMode, QueueCommand};
+use crate::bencher::render_list;
+use crate::config::Config;
+use crate::publisher;
+use crate::DynResult;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The request queue. Every line is a `skyreport` invocation, and lines are only
+/// ever appended
+pub const FILE_REQUESTS: &str = "requests.txt";
+/// Keeps track of the requests that have already been processed
+pub const FILE_QUEUE_STATE: &str = "requests.state.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// The job ran successfully
+    Done,
+    /// The job ran, but failed
+    Failed,
+    /// The request couldn't be parsed
+    Invalid,
+    /// The request was skipped without being run
+    Skipped,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// A request that has already been processed
+pub struct Processed {
+    /// The line number (starting at 1) in the request file
+    pub line: usize,
+    pub request: String,
+    pub status: Status,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QueueState {
+    pub processed: Vec<Processed>,
+}
+
+#[derive(Debug)]
+/// A request that is yet to be processed
+pub struct Job {
+    pub line: usize,
+    pub request: String,
+    pub action: Result<Action, String>,
+}
+
+/// Parses a request of the form `path/to/skyreport <args>`. Requests are never passed
+/// through a shell; they're only split on whitespace
+pub fn parse_request(request: &str) -> Result<Action, String> {
+    let mut tokens = request.split_whitespace();
+    match tokens.next() {
+        Some(program) if program.ends_with("skyreport") => {}
+        _ => return Err("requests must start with the `skyreport` binary".to_owned()),
+    }
+    let args =
+        Args::from_args(std::iter::once("skyreport").chain(tokens)).map_err(|e| e.to_string())?;
+    match args.action {
+        Action::Queue(_) => Err("requests can't operate on the queue".to_owned()),
+        action => Ok(action),
+    }
+}
+
+pub struct Queue {
+    lines: Vec<String>,
+    state: QueueState,
+}
+
+impl Queue {
+    pub fn new(requests: &str, state: QueueState) -> Self {
+        Self {
+            lines: requests.lines().map(ToOwned::to_owned).collect(),
+            state,
+        }
+    }
+    pub fn load() -> DynResult<Self> {
+        let requests = fs::read_to_string(FILE_REQUESTS)?;
+        let state = if Path::new(FILE_QUEUE_STATE).exists() {
+            serde_json::from_str(&fs::read_to_string(FILE_QUEUE_STATE)?)?
+        } else {
+            info!("No queue state found. Every request is pending");
+            QueueState::default()
+        };
+        let queue = Self::new(&requests, state);
+        queue.check_history()?;
+        Ok(queue)
+    }
+    /// Warns if the requests that were already processed have been changed since, and
+    /// fails if the state refers to a line that isn't in the requests at all
+    fn check_history(&self) -> DynResult<()> {
+        for entry in self.state.processed.iter() {
+            if entry.line == 0 || entry.line > self.lines.len() {
+                return rerr!(format!(
+                    "Bad state in `{}`: line {} is out of range (`{}` has {} line(s))",
+                    FILE_QUEUE_STATE,
+                    entry.line,
+                    FILE_REQUESTS,
+                    self.lines.len()
+                ));
+            }
+            if self.lines[entry.line - 1] != entry.request {
+                warn!(
+                    "Line {} of `{}` has changed since it was processed",
+                    entry.line, FILE_REQUESTS
+                );
+            }
+        }
+        Ok(())
+    }
+    /// Returns every request after the last processed one, in order
+    pub fn pending(&self) -> Vec<Job> {
+        let last = self.state.processed.last().map_or(0, |entry| entry.line);
+        self.lines
+            .iter()
+            .enumerate()
+            .map(|(idx, request)| (idx + 1, request.trim()))
+            .filter(|(line, request)| {
+                *line > last && !request.is_empty() && !request.starts_with('#')
+            })
+            .map(|(line, request)| Job {
+                line,
+                request: request.to_owned(),
+                action: parse_request(request),
+            })
+            .collect()
+    }
+    /// Record the status of the request on `line`
+    pub fn record(&mut self, line: usize, status: Status) {
+        self.state.processed.push(Processed {
+            line,
+            request: self.lines[line - 1].clone(),
+            status,
+        });
+    }
+    pub fn state(&self) -> &QueueState {
+        &self.state
+    }
+}
+
+/// Run a queue command. Jobs use the publish mode and configuration of the queue,
+/// and the queue state is published after every job
+pub async fn run(cfg: &Config, mode: PublishMode, command: QueueCommand) -> DynResult<()> {
+    let mut queue = Queue::load()?;
+    let pending = queue.pending();
+    if let QueueCommand::List = command {
+        let items = pending
+            .iter()
+            .map(|job| match job.action {
+                Ok(_) => format!("{}: `{}`", job.line, job.request),
+                Err(_) => format!("{}: `{}` (invalid)", job.line, job.request),
+            })
+            .collect();
+        info!("{} pending request(s)", pending.len());
+        print!("{}", render_list(items));
+        return Ok(());
+    }
+    let publisher = publisher::new(cfg, mode)?;
+    let mut failed = 0;
+    for job in pending {
+        let status = match (&command, job.action) {
+            (QueueCommand::Skip, _) => {
+                info!("Skipping request on line {}: `{}`", job.line, job.request);
+                Status::Skipped
+            }
+            (_, Err(ref e)) => {
+                error!("Bad request on line {}: `{}`\n{}", job.line, job.request, e);
+                Status::Invalid
+            }
+            (_, Ok(action)) => {
+                info!("Running request on line {}: `{}`", job.line, job.request);
+                // boxed, since this is indirectly recursive
+                match Box::pin(crate::execute(cfg, mode, action)).await {
+                    Ok(()) => Status::Done,
+                    Err(e) => {
+                        error!("Request on line {} failed with: {}", job.line, e);
+                        Status::Failed
+                    }
+                }
+            }
+        };
+        if status != Status::Done && status != Status::Skipped {
+            failed += 1;
+        }
+        queue.record(job.line, status);
+        publisher.write_file(
+            FILE_QUEUE_STATE,
+            serde_json::to_string_pretty(queue.state())?.as_bytes(),
+        )?;
+        publisher.commit(&[format!("Processed request on line {} [skip ci]", job.line)])?;
+    }
+    if failed != 0 {
+        return rerr!(format!("{} request(s) failed", failed));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parse_request() {
+    use crate::args::NewBench;
+    assert_eq!(
+        parse_request("target/release/skyreport bench 4d2c7eaebf 222"),
+        Ok(Action::NewBench(NewBench::new(
+            "4d2c7eaebf".to_owned(),
+            Some(222),
+            false
+        )))
+    );
+    assert_eq!(
+        parse_request("target/release/skyreport update next"),
+        Ok(Action::UpdateNext)
+    );
+    assert!(parse_request("target/release/skyreport update nexttarget/release/skyreport").is_err());
+    assert!(parse_request("rm -rf / && target/release/skyreport update next").is_err());
+    assert!(parse_request("target/release/skyreport queue run").is_err());
+}
+
+#[test]
+fn test_pending() {
+    let requests =
+        "skyreport update next\n\nskyreport bench abcd 1\n# comment\nbad\nskyreport update next\n";
+    let mut queue = Queue::new(requests, QueueState::default());
+    let lines: Vec<usize> = queue.pending().iter().map(|job| job.line).collect();
+    assert_eq!(lines, vec![1, 3, 5, 6]);
+    queue.record(1, Status::Done);
+    queue.record(3, Status::Failed);
+    let pending = queue.pending();
+    assert_eq!(pending.len(), 2);
+    assert!(pending[0].action.is_err());
+    assert_eq!(pending[1].action, Ok(Action::UpdateNext));
+    assert_eq!(queue.state().processed[1].request, "skyreport bench abcd 1");
+}
+
+#[test]
+fn test_check_history() {
+    let state = |line| QueueState {
+        processed: vec![Processed {
+            line,
+            request: "skyreport update next".to_owned(),
+            status: Status::Done,
+        }],
+    };
+    let requests = "skyreport update next\nskyreport bench abcd 1\n";
+    assert!(Queue::new(requests, state(1)).check_history().is_ok());
+    // a changed request is only a warning
+    assert!(Queue::new(requests, state(2)).check_history().is_ok());
+    // but a hand-edited state can't point outside the requests
+    assert!(Queue::new(requests, state(0)).check_history().is_err());
+    assert!(Queue::new(requests, state(3)).check_history().is_err());
+}
@@ -0,0 +1,288 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::bencher::Metrics;
+use crate::environment;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+/// A single reading of a process' resource usage from `/proc`
+struct Sample {
+    rss_kib: u64,
+    /// The CPU time in user and kernel mode, in clock ticks
+    cpu_ticks: u64,
+    threads: u64,
+    fds: u64,
+    read_bytes: u64,
+    write_bytes: u64,
+}
+
+/// Returns the number in the first `key: value` line of `input` with the given key
+/// (ignoring the unit, if there is one)
+fn status_field(input: &str, key: &str) -> Option<u64> {
+    environment::field(input, key)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Returns the user and system CPU time (in clock ticks) from `/proc/<pid>/stat`
+fn parse_stat(stat: &str) -> Option<u64> {
+    // the name (in the second field) can have spaces and parentheses, so we start
+    // after the last `)`. The first field after that is the state (field 3)
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    let utime: u64 = fields.get(14 - 3)?.parse().ok()?;
+    let stime: u64 = fields.get(15 - 3)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+impl Sample {
+    /// Read the resource usage of `pid`, returning `None` if the process is gone (or
+    /// `/proc` couldn't be read)
+    fn read(pid: u32) -> Option<Self> {
+        let proc = format!("/proc/{}", pid);
+        let status = fs::read_to_string(format!("{}/status", proc)).ok()?;
+        let stat = fs::read_to_string(format!("{}/stat", proc)).ok()?;
+        // io isn't available on every kernel
+        let io = fs::read_to_string(format!("{}/io", proc)).unwrap_or_default();
+        Some(Self {
+            rss_kib: status_field(&status, "VmRSS")?,
+            cpu_ticks: parse_stat(&stat)?,
+            threads: status_field(&status, "Threads")?,
+            fds: fs::read_dir(format!("{}/fd", proc)).ok()?.count() as u64,
+            read_bytes: status_field(&io, "read_bytes").unwrap_or_default(),
+            write_bytes: status_field(&io, "write_bytes").unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+/// A summary of the server's resource usage during a benchmark
+pub struct ResourceUsage {
+    pub samples: usize,
+    pub peak_rss_kib: u64,
+    pub avg_rss_kib: u64,
+    /// The CPU time (user and kernel) used
+    pub cpu_secs: f64,
+    pub peak_threads: u64,
+    pub peak_fds: u64,
+    /// The bytes read from and written to disk
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+impl ResourceUsage {
+    fn from_samples(samples: &[Sample]) -> Option<Self> {
+        let (first, last) = (samples.first()?, samples.last()?);
+        let peak = |f: fn(&Sample) -> u64| samples.iter().map(f).max().unwrap_or_default();
+        // CPU time and IO are cumulative (since the process started), so only what was
+        // used between the first and the last sample counts
+        let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f64;
+        Some(Self {
+            samples: samples.len(),
+            peak_rss_kib: peak(|s| s.rss_kib),
+            avg_rss_kib: samples.iter().map(|s| s.rss_kib).sum::<u64>() / samples.len() as u64,
+            cpu_secs: last.cpu_ticks.saturating_sub(first.cpu_ticks) as f64 / ticks_per_sec,
+            peak_threads: peak(|s| s.threads),
+            peak_fds: peak(|s| s.fds),
+            read_bytes: last.read_bytes.saturating_sub(first.read_bytes),
+            write_bytes: last.write_bytes.saturating_sub(first.write_bytes),
+        })
+    }
+    /// Combine the usage from multiple sessions: the peaks are the highest peaks, and
+    /// everything else is the mean across the sessions
+    pub fn combine(usages: &[ResourceUsage]) -> Option<Self> {
+        if usages.is_empty() {
+            return None;
+        }
+        let n = usages.len() as u64;
+        let peak = |f: fn(&ResourceUsage) -> u64| usages.iter().map(f).max().unwrap_or_default();
+        let mean = |f: fn(&ResourceUsage) -> u64| usages.iter().map(f).sum::<u64>() / n;
+        Some(Self {
+            samples: usages.iter().map(|u| u.samples).sum(),
+            peak_rss_kib: peak(|u| u.peak_rss_kib),
+            avg_rss_kib: mean(|u| u.avg_rss_kib),
+            cpu_secs: usages.iter().map(|u| u.cpu_secs).sum::<f64>() / n as f64,
+            peak_threads: peak(|u| u.peak_threads),
+            peak_fds: peak(|u| u.peak_fds),
+            read_bytes: mean(|u| u.read_bytes),
+            write_bytes: mean(|u| u.write_bytes),
+        })
+    }
+    /// Returns the name, the raw value and the formatted value for every metric
+    pub fn metrics(&self) -> Metrics {
+        let mib = |kib: u64| kib as f64 / 1024.0;
+        let bytes_mib = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+        vec![
+            (
+                "Peak RSS",
+                self.peak_rss_kib as f64,
+                format!("{:.1} MiB", mib(self.peak_rss_kib)),
+            ),
+            (
+                "Average RSS",
+                self.avg_rss_kib as f64,
+                format!("{:.1} MiB", mib(self.avg_rss_kib)),
+            ),
+            ("CPU time", self.cpu_secs, format!("{:.2} s", self.cpu_secs)),
+            (
+                "Peak threads",
+                self.peak_threads as f64,
+                self.peak_threads.to_string(),
+            ),
+            (
+                "Peak open FDs",
+                self.peak_fds as f64,
+                self.peak_fds.to_string(),
+            ),
+            (
+                "Disk read",
+                self.read_bytes as f64,
+                format!("{:.1} MiB", bytes_mib(self.read_bytes)),
+            ),
+            (
+                "Disk written",
+                self.write_bytes as f64,
+                format!("{:.1} MiB", bytes_mib(self.write_bytes)),
+            ),
+        ]
+    }
+}
+
+/// Samples the resource usage of a process in the background, at a fixed interval. The
+/// first sample is taken right away, so the usage is for the time from `start` to
+/// `finish`
+pub struct Sampler {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Vec<Sample>>,
+}
+
+impl Sampler {
+    pub fn start(pid: u32, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let handle = thread::spawn(move || {
+            let mut samples = Vec::new();
+            while !stopped.load(Ordering::Acquire) {
+                match Sample::read(pid) {
+                    Some(sample) => samples.push(sample),
+                    // the process exited
+                    None => break,
+                }
+                thread::park_timeout(interval);
+            }
+            samples
+        });
+        Self { stop, handle }
+    }
+    /// Stop sampling, returning the summary (if there were any samples)
+    pub fn finish(self) -> Option<ResourceUsage> {
+        self.stop.store(true, Ordering::Release);
+        self.handle.thread().unpark();
+        let samples = self.handle.join().unwrap_or_default();
+        ResourceUsage::from_samples(&samples)
+    }
+}
+
+#[test]
+fn test_parse_proc() {
+    let stat = "1234 (sky d) S 1 1234 1234 0 -1 4194560 1024 0 0 0 150 50 0 0 20 0 9 0 1000";
+    assert_eq!(parse_stat(stat), Some(200));
+    let status = "Name:\tskyd\nVmRSS:\t   10240 kB\nThreads:\t9\n";
+    assert_eq!(status_field(status, "VmRSS"), Some(10240));
+    assert_eq!(status_field(status, "Threads"), Some(9));
+    assert_eq!(status_field(status, "VmSwap"), None);
+}
+
+#[test]
+fn test_sampler() {
+    let mut child = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .unwrap();
+    let sampler = Sampler::start(child.id(), Duration::from_millis(10));
+    thread::sleep(Duration::from_millis(100));
+    let usage = sampler.finish().unwrap();
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(usage.samples > 1);
+    assert!(usage.peak_rss_kib > 0);
+    assert_eq!(usage.peak_threads, 1);
+    let combined = ResourceUsage::combine(&[usage.clone(), usage.clone()]).unwrap();
+    assert_eq!(combined.peak_rss_kib, usage.peak_rss_kib);
+    assert_eq!(combined.samples, usage.samples * 2);
+}
+
+#[test]
+fn test_sampled_window() {
+    let sample = |cpu_ticks, rss_kib, write_bytes| Sample {
+        rss_kib,
+        cpu_ticks,
+        threads: 1,
+        fds: 3,
+        read_bytes: 0,
+        write_bytes,
+    };
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as u64;
+    // the process had used CPU and written to disk before sampling started
+    let usage = ResourceUsage::from_samples(&[
+        sample(5 * ticks_per_sec, 100, 4096),
+        sample(6 * ticks_per_sec, 300, 8192),
+        sample(7 * ticks_per_sec, 200, 8192),
+    ])
+    .unwrap();
+    assert_eq!(usage.cpu_secs, 2.0);
+    assert_eq!(usage.write_bytes, 4096);
+    assert_eq!(usage.peak_rss_kib, 300);
+    assert_eq!(usage.avg_rss_kib, 200);
+    // and the same goes for a real process that was busy before sampling started
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    let mut child = Command::new("sh")
+        .args([
+            "-c",
+            "i=0; while [ $i -lt 200000 ]; do i=$((i+1)); done; echo busy; exec sleep 30",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    let before = Sample::read(child.id()).unwrap();
+    assert!(before.cpu_ticks > 0);
+    let sampler = Sampler::start(child.id(), Duration::from_millis(10));
+    thread::sleep(Duration::from_millis(100));
+    let usage = sampler.finish().unwrap();
+    child.kill().unwrap();
+    child.wait().unwrap();
+    assert!(usage.cpu_secs < before.cpu_ticks as f64 / ticks_per_sec as f64);
+}
@@ -0,0 +1,202 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::sampler::{ResourceUsage, Sampler};
+use crate::DynResult;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often we check if the server has exited
+const WAIT_INTERVAL: Duration = Duration::from_millis(100);
+/// If this shows up in the server's output, the server panicked
+const PANIC_MARKER: &str = "panicked at";
+
+/// A running server. The server is stopped when the guard is dropped, but [`Self::stop`]
+/// should be used to find out if the server misbehaved
+pub struct ServerGuard {
+    child: Option<Child>,
+    log: PathBuf,
+    shutdown_timeout: Duration,
+    sampler: Option<Sampler>,
+}
+
+impl ServerGuard {
+    /// Spawn the server with its stdout and stderr redirected to `log`
+    pub fn spawn(mut cmd: Command, log: &Path, shutdown_timeout: Duration) -> DynResult<Self> {
+        let file = fs::File::create(log)?;
+        let child = cmd
+            .stdin(Stdio::null())
+            .stdout(file.try_clone()?)
+            .stderr(file)
+            .spawn()?;
+        Ok(Self {
+            child: Some(child),
+            log: log.to_path_buf(),
+            shutdown_timeout,
+            sampler: None,
+        })
+    }
+    pub fn child_mut(&mut self) -> &mut Child {
+        // the child is only taken when stopping, which consumes the guard
+        self.child.as_mut().unwrap()
+    }
+    /// Start sampling the server's resource usage every `interval`
+    pub fn start_sampling(&mut self, interval: Duration) {
+        let pid = self.child_mut().id();
+        self.sampler = Some(Sampler::start(pid, interval));
+    }
+    /// Stop sampling, returning the resource usage since sampling was started (if it
+    /// was started)
+    pub fn finish_sampling(&mut self) -> Option<ResourceUsage> {
+        self.sampler.take().and_then(Sampler::finish)
+    }
+    /// Stop the server, returning the reason the run should be considered invalid
+    /// (if the server exited early or panicked)
+    pub fn stop(mut self) -> DynResult<Option<String>> {
+        let mut child = self.child.take().unwrap();
+        let mut problem = match child.try_wait()? {
+            Some(status) => Some(format!(
+                "the server exited during the benchmark ({})",
+                status
+            )),
+            None => {
+                let status = terminate(&mut child, self.shutdown_timeout)?;
+                if !status.success() {
+                    warn!("The server exited with {} on shutdown", status);
+                }
+                None
+            }
+        };
+        let output = fs::read_to_string(&self.log).unwrap_or_default();
+        if output.contains(PANIC_MARKER) {
+            problem = Some("the server panicked".to_owned());
+        }
+        if let Some(ref problem) = problem {
+            error!(
+                "The run is invalid: {}. See the log at `{}`",
+                problem,
+                self.log.display()
+            );
+        }
+        Ok(problem)
+    }
+}
+
+impl Drop for ServerGuard {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            if let Err(e) = terminate(&mut child, self.shutdown_timeout) {
+                error!("Failed to stop server: {}", e);
+            }
+        }
+    }
+}
+
+/// Sends SIGTERM to the child and waits for it to exit, sending SIGKILL if it
+/// doesn't exit within `timeout`. The child is always reaped
+fn terminate(child: &mut Child, timeout: Duration) -> DynResult<ExitStatus> {
+    info!("Sending SIGTERM to server (pid {}) ...", child.id());
+    if unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) } == 0 {
+        if let Some(status) = wait_timeout(child, timeout)? {
+            return Ok(status);
+        }
+        warn!("The server didn't exit within {:?}", timeout);
+    } else {
+        warn!(
+            "Failed to send SIGTERM to server: {}",
+            io::Error::last_os_error()
+        );
+    }
+    info!("Sending SIGKILL to server ...");
+    child.kill()?;
+    Ok(child.wait()?)
+}
+
+/// Waits for the child to exit for up to `timeout`
+fn wait_timeout(child: &mut Child, timeout: Duration) -> DynResult<Option<ExitStatus>> {
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if start.elapsed() > timeout {
+            return Ok(None);
+        }
+        thread::sleep(WAIT_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+fn test_log(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("skyreport-{}-{}.log", name, std::process::id()))
+}
+
+#[test]
+fn test_stop_running_server() {
+    let log = test_log("stop");
+    let server = ServerGuard::spawn(cmd!("sleep", "30"), &log, Duration::from_secs(5)).unwrap();
+    assert_eq!(server.stop().unwrap(), None);
+    fs::remove_file(log).unwrap();
+}
+
+#[test]
+fn test_stop_server_escalates() {
+    let log = test_log("escalate");
+    let server = ServerGuard::spawn(
+        cmd!("sh", "-c", "trap '' TERM; echo ready; sleep 30"),
+        &log,
+        Duration::from_millis(300),
+    )
+    .unwrap();
+    // give the shell some time to set up the trap
+    thread::sleep(Duration::from_millis(200));
+    let start = Instant::now();
+    assert_eq!(server.stop().unwrap(), None);
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(fs::read_to_string(&log).unwrap(), "ready\n");
+    fs::remove_file(log).unwrap();
+}
+
+#[test]
+fn test_stop_crashed_server() {
+    let log = test_log("crash");
+    let server = ServerGuard::spawn(
+        cmd!(
+            "sh",
+            "-c",
+            "echo \"thread 'main' panicked at 'oops'\"; exit 101"
+        ),
+        &log,
+        Duration::from_secs(5),
+    )
+    .unwrap();
+    thread::sleep(Duration::from_millis(200));
+    let problem = server.stop().unwrap().unwrap();
+    assert_eq!(problem, "the server panicked");
+    fs::remove_file(log).unwrap();
+}
@@ -0,0 +1,247 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+//! A minimal Skyhash (1.0) client. Every query is a simple query (a single array of
+//! strings) and every response has a single element, which is all we need to run
+//! `GET`, `SET` and `UPDATE`
+
+use crate::DynResult;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream as AsyncTcpStream;
+
+#[derive(Debug, PartialEq)]
+/// A single element in a response, like `+5\nhello\n` or `!1\n0\n`
+pub struct Element {
+    /// The type symbol (`+` for a string, `!` for a response code, ...)
+    pub tsymbol: u8,
+    pub data: Vec<u8>,
+}
+
+impl Element {
+    /// Returns true if this is a response code other than `0` (okay)
+    pub fn is_error(&self) -> bool {
+        self.tsymbol == b'!' && self.data != b"0"
+    }
+}
+
+/// Encode a simple query with the provided arguments
+pub fn query<T: AsRef<[u8]>>(args: &[T]) -> Vec<u8> {
+    let mut buf = format!("*1\n~{}\n", args.len()).into_bytes();
+    for arg in args {
+        let arg = arg.as_ref();
+        buf.extend_from_slice(format!("{}\n", arg.len()).as_bytes());
+        buf.extend_from_slice(arg);
+        buf.push(b'\n');
+    }
+    buf
+}
+
+/// Returns the line starting at `from` (without the `\n`) and the position after it,
+/// or `None` if the line isn't complete yet
+fn line(buf: &[u8], from: usize) -> Option<(&[u8], usize)> {
+    let len = buf.get(from..)?.iter().position(|b| *b == b'\n')?;
+    Some((&buf[from..from + len], from + len + 1))
+}
+
+/// Parse a response with a single element, returning the element and the number of
+/// bytes it took up. This returns `Ok(None)` if the response isn't complete yet
+pub fn parse(buf: &[u8]) -> DynResult<Option<(Element, usize)>> {
+    let (header, pos) = match line(buf, 0) {
+        Some(line) => line,
+        None => return Ok(None),
+    };
+    if header != b"*1" {
+        return rerr!(format!(
+            "Unexpected response header `{}`",
+            String::from_utf8_lossy(header)
+        ));
+    }
+    let (size, pos) = match line(buf, pos) {
+        Some((size, pos)) if !size.is_empty() => (size, pos),
+        Some(_) => return rerr!("Empty element in response"),
+        None => return Ok(None),
+    };
+    let tsymbol = size[0];
+    let len: usize = match String::from_utf8_lossy(&size[1..]).parse() {
+        Ok(len) => len,
+        Err(_) => {
+            return rerr!(format!(
+                "Bad element size `{}`",
+                String::from_utf8_lossy(size)
+            ))
+        }
+    };
+    // the data, and then a `\n`
+    if buf.len() < pos + len + 1 {
+        return Ok(None);
+    }
+    let data = buf[pos..pos + len].to_vec();
+    Ok(Some((Element { tsymbol, data }, pos + len + 1)))
+}
+
+/// A blocking connection to the server
+pub struct Connection {
+    con: TcpStream,
+    buf: Vec<u8>,
+}
+
+impl Connection {
+    pub fn connect(addr: SocketAddr, timeout: Duration) -> DynResult<Self> {
+        let con = TcpStream::connect_timeout(&addr, timeout)?;
+        con.set_read_timeout(Some(timeout))?;
+        con.set_nodelay(true)?;
+        Ok(Self {
+            con,
+            buf: Vec::with_capacity(1024),
+        })
+    }
+    /// Send an encoded query (see [`query`]) and wait for the response
+    pub fn run(&mut self, query: &[u8]) -> DynResult<Element> {
+        self.con.write_all(query)?;
+        self.buf.clear();
+        let mut chunk = [0u8; 1024];
+        loop {
+            if let Some((element, _)) = parse(&self.buf)? {
+                return Ok(element);
+            }
+            let read = self.con.read(&mut chunk)?;
+            if read == 0 {
+                return rerr!("The server closed the connection");
+            }
+            self.buf.extend_from_slice(&chunk[..read]);
+        }
+    }
+}
+
+/// A non-blocking connection to the server
+pub struct AsyncConnection {
+    con: AsyncTcpStream,
+    buf: Vec<u8>,
+}
+
+impl AsyncConnection {
+    pub async fn connect(addr: SocketAddr) -> io::Result<Self> {
+        let con = AsyncTcpStream::connect(addr).await?;
+        con.set_nodelay(true)?;
+        Ok(Self {
+            con,
+            buf: Vec::with_capacity(1024),
+        })
+    }
+    /// Send an encoded query (see [`query`]) and wait for the response
+    pub async fn run(&mut self, query: &[u8]) -> io::Result<Element> {
+        self.con.write_all(query).await?;
+        self.buf.clear();
+        let mut chunk = [0u8; 1024];
+        loop {
+            // the error isn't `Send`, so it's turned into an IO error
+            match parse(&self.buf) {
+                Ok(Some((element, _))) => return Ok(element),
+                Ok(None) => {}
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+            }
+            let read = self.con.read(&mut chunk).await?;
+            if read == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            self.buf.extend_from_slice(&chunk[..read]);
+        }
+    }
+}
+
+#[test]
+fn test_skyhash() {
+    assert_eq!(
+        query(&["SET", "x", "100"]),
+        b"*1\n~3\n3\nSET\n1\nx\n3\n100\n".to_vec()
+    );
+    let (element, len) = parse(b"*1\n+5\nhello\n*1").unwrap().unwrap();
+    assert_eq!(element.data, b"hello");
+    assert_eq!(len, 12);
+    assert!(!element.is_error());
+    // the overwrite error
+    let (element, _) = parse(b"*1\n!1\n2\n").unwrap().unwrap();
+    assert!(element.is_error());
+    // incomplete responses
+    assert!(parse(b"*1\n+5\nhel").unwrap().is_none());
+    assert!(parse(b"*1\n+5").unwrap().is_none());
+    assert!(parse(b"HTTP/1.1 400\n").is_err());
+}
+
+#[cfg(test)]
+/// A fake Skyhash server for the tests
+pub mod fake {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::thread;
+
+    /// Returns the length of the first complete query in `buf` and its action
+    fn next_query(buf: &[u8]) -> Option<(usize, String)> {
+        let lines: Vec<&[u8]> = buf.split(|b| *b == b'\n').collect();
+        // the last "line" is whatever comes after the last `\n`
+        let complete = lines.len() - 1;
+        let args: usize = String::from_utf8_lossy(lines.get(1)?.get(1..)?)
+            .parse()
+            .ok()?;
+        let len = 2 + 2 * args;
+        if complete < len {
+            return None;
+        }
+        let size = lines[..len].iter().map(|l| l.len() + 1).sum();
+        Some((size, String::from_utf8_lossy(lines[3]).into_owned()))
+    }
+
+    /// Start a fake server on a free port, with a thread for every connection, and
+    /// return the port. `reply` returns the response to every query, given its action
+    pub fn start(reply: impl Fn(&str) -> &'static [u8] + Send + Sync + 'static) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let reply = Arc::new(reply);
+        thread::spawn(move || {
+            for con in listener.incoming() {
+                let mut con = con.unwrap();
+                let reply = reply.clone();
+                thread::spawn(move || {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 1024];
+                    loop {
+                        while let Some((len, action)) = next_query(&buf) {
+                            buf.drain(..len);
+                            con.write_all(reply(&action)).unwrap();
+                        }
+                        match con.read(&mut chunk) {
+                            Ok(0) | Err(_) => break,
+                            Ok(read) => buf.extend_from_slice(&chunk[..read]),
+                        }
+                    }
+                });
+            }
+        });
+        port
+    }
+}
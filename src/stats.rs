@@ -0,0 +1,106 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// The spread of a set of samples (for example, the GET throughput across every run)
+pub struct Spread {
+    pub samples: Vec<f32>,
+    pub mean: f32,
+    pub median: f32,
+    pub min: f32,
+    pub max: f32,
+    /// The (sample) standard deviation
+    pub stddev: f32,
+    /// The coefficient of variation, as a percentage
+    pub cv: f32,
+}
+
+impl Spread {
+    /// Computes the spread of the provided samples. There must be at least one sample
+    pub fn from_samples(samples: Vec<f32>) -> Self {
+        assert!(!samples.is_empty(), "no samples");
+        let n = samples.len() as f32;
+        let mean = samples.iter().sum::<f32>() / n;
+        let mut sorted = samples.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let mid = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {
+            (sorted[mid - 1] + sorted[mid]) / 2.0
+        } else {
+            sorted[mid]
+        };
+        let stddev = if samples.len() > 1 {
+            let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / (n - 1.0);
+            variance.sqrt()
+        } else {
+            0.0
+        };
+        Self {
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            cv: if mean == 0.0 {
+                0.0
+            } else {
+                stddev / mean * 100.0
+            },
+            samples,
+            mean,
+            median,
+            stddev,
+        }
+    }
+    /// Returns a short summary, like `median 12.0, min 10.0, max 14.0, σ 2.0, CV 16.67%, n=3`
+    pub fn summary(&self) -> String {
+        format!(
+            "median {:.2}, min {:.2}, max {:.2}, σ {:.2}, CV {:.2}%, n={}",
+            self.median,
+            self.min,
+            self.max,
+            self.stddev,
+            self.cv,
+            self.samples.len()
+        )
+    }
+}
+
+#[test]
+fn test_spread() {
+    let spread = Spread::from_samples(vec![14.0, 10.0, 12.0]);
+    assert_eq!(spread.mean, 12.0);
+    assert_eq!(spread.median, 12.0);
+    assert_eq!(spread.min, 10.0);
+    assert_eq!(spread.max, 14.0);
+    assert_eq!(spread.stddev, 2.0);
+    assert!((spread.cv - 16.666_668).abs() < 1e-4);
+    assert_eq!(
+        spread.summary(),
+        "median 12.00, min 10.00, max 14.00, σ 2.00, CV 16.67%, n=3"
+    );
+    let spread = Spread::from_samples(vec![1.0, 4.0, 2.0, 3.0]);
+    assert_eq!(spread.median, 2.5);
+    let spread = Spread::from_samples(vec![5.0]);
+    assert_eq!((spread.mean, spread.stddev, spread.cv), (5.0, 0.0, 0.0));
+}
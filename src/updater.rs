@@ -22,12 +22,23 @@
  *
 */
 
-use crate::bencher::Report;
-use crate::util;
+use crate::bencher::{MatrixCell, Report};
+use crate::cache::{self, Cache};
+use crate::config::{Client, Config, Workload};
+use crate::environment::Environment;
+use crate::latency::{self, LatencyReport};
+use crate::loadgen;
+use crate::persistence::{self, Persistence};
+use crate::publisher::Publisher;
+use crate::sampler::ResourceUsage;
+use crate::util::{self, Workspace};
+use crate::watchdog::{self, Stage, Watchdog};
 use crate::DynResult;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 pub type SkyBenchReport = Vec<SkyBenchReportSection>;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,73 +48,744 @@ pub struct SkyBenchReportSection {
 }
 
 pub const FILE_LATEST_RELEASE: &str = "./preset/release.json";
-const BRANCH_LATEST: &str = "next";
 pub const FILE_NEXT: &str = "./preset/next.json";
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ReportItem {
     pub commit: String,
     pub report: Report,
+    /// The report for every cell of the workload matrix, if one was configured
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matrix: Vec<MatrixCell>,
+    /// The machine and toolchain that the preset was benched on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,
+    /// The server's resource usage while the preset was benched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceUsage>,
+    /// The latency distribution of every operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyReport>,
+    /// The client that generated the load
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<ClientInfo>,
+    /// The result of the persistence scenario, if it was run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persistence: Option<Persistence>,
 }
 
 impl ReportItem {
-    pub const fn new(commit: String, report: Report) -> Self {
-        Self { commit, report }
+    /// Returns the preset for the result of benching `commit`, failing if the run was
+    /// invalid
+    pub fn from_result(commit: String, result: &RawResult) -> DynResult<Self> {
+        Ok(Self {
+            commit,
+            report: result.valid_report()?,
+            matrix: result.matrix()?,
+            environment: Some(result.environment.clone()),
+            resources: result.resources(),
+            latency: result.latency()?,
+            client: Some(result.client.clone()),
+            persistence: result.persistence.clone(),
+        })
     }
 }
 
 /// Updates the release preset result to the provided release.
 /// **Be warned! You should supply the latest release**
-pub fn update_release(release: &str) -> DynResult<()> {
+pub fn update_release(cfg: &Config, publisher: &dyn Publisher, release: &str) -> DynResult<()> {
     info!(
         "Updating results for latests release (assuming `{}` is latest)",
         release
     );
-    let results: Report = Report::from_stdout(self::raw_result(release)?)?;
-    let result_update = ReportItem::new(release.to_owned(), results);
+    let result = self::raw_result(cfg, release)?;
+    let result_update = ReportItem::from_result(release.to_owned(), &result)?;
     let result_update_str = serde_json::to_string_pretty(&result_update)?;
-    util::create_and_write_to_file(FILE_LATEST_RELEASE, result_update_str.as_bytes())?;
-    commit!(format!(
+    publisher.write_file(FILE_LATEST_RELEASE, result_update_str.as_bytes())?;
+    publisher.commit(&[format!(
         "Update results for release `{}` [skip ci]",
         release
-    ));
+    )])?;
     Ok(())
 }
 
-/// Updates the next preset result to the current `HEAD` on `skytable/skytable`
-/// (`next`)
-pub fn update_next() -> DynResult<()> {
-    info!("Updating results for next ...",);
-    let results: Report = Report::from_stdout(self::raw_result(BRANCH_LATEST)?)?;
-    let result_update = ReportItem::new(util::get_latest_commit()?, results);
+/// Updates the next preset result to the current `HEAD` on the configured branch
+/// (`next` by default), returning the refreshed preset
+pub fn update_next(cfg: &Config, publisher: &dyn Publisher) -> DynResult<ReportItem> {
+    info!("Updating results for `{}` ...", cfg.repo.branch);
+    let result = self::raw_result(cfg, &cfg.repo.branch)?;
+    let result_update = ReportItem::from_result(result.commit.clone(), &result)?;
     let result_update_str = serde_json::to_string_pretty(&result_update)?;
-    util::create_and_write_to_file(FILE_NEXT, result_update_str.as_bytes())?;
-    commit!("Update results for next [skip ci]");
-    Ok(())
+    publisher.write_file(FILE_NEXT, result_update_str.as_bytes())?;
+    publisher.commit(&[format!("Update results for {} [skip ci]", cfg.repo.branch)])?;
+    Ok(result_update)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// The client that generated the load
+pub struct ClientInfo {
+    pub client: Client,
+    /// The commit that `sky-bench` was built from (not set for the native client)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// The ref that `sky-bench` was pinned to, if it wasn't built from the benchmarked
+    /// ref
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned: Option<String>,
+}
+
+impl ClientInfo {
+    /// Returns a description of the client for the report
+    pub fn summary(&self) -> String {
+        match (self.client, &self.commit, &self.pinned) {
+            (Client::Native, _, _) => "native load generator".to_owned(),
+            (Client::SkyBench, Some(commit), Some(pinned)) => {
+                format!("sky-bench, pinned to `{}` ({})", pinned, commit)
+            }
+            (Client::SkyBench, Some(commit), None) => {
+                format!("sky-bench, built from the benchmarked commit ({})", commit)
+            }
+            (Client::SkyBench, None, _) => "sky-bench".to_owned(),
+        }
+    }
+    /// Returns true if the client doesn't change with the benchmarked ref (that is, if
+    /// it is the native client or `sky-bench` was pinned)
+    pub fn is_fixed(&self) -> bool {
+        self.client == Client::Native || self.pinned.is_some()
+    }
+    /// Returns how this client differs from `other`, if this client is fixed (see
+    /// [`Self::is_fixed`]) and the results were expected to come from the same client
+    pub fn difference(&self, other: &ClientInfo) -> Option<String> {
+        if self.is_fixed() && (self.client != other.client || self.commit != other.commit) {
+            Some(format!("{} v/s {}", self.summary(), other.summary()))
+        } else {
+            None
+        }
+    }
+}
+
+/// The result of every iteration of a single workload
+pub struct WorkloadRuns {
+    pub workload: Workload,
+    pub runs: Vec<Report>,
+}
+
+impl WorkloadRuns {
+    /// Returns the report aggregated across every iteration
+    pub fn report(&self) -> DynResult<Report> {
+        Report::from_runs(&self.runs)
+    }
+}
+
+/// The output of benchmarking a single ref
+pub struct RawResult {
+    /// The commit that the ref resolved to
+    pub commit: String,
+    /// The runs for every workload. The first one is the primary workload
+    pub workloads: Vec<WorkloadRuns>,
+    /// The result of every warm-up run. These aren't measurements
+    pub warmup: Vec<Report>,
+    /// The time the server took to start accepting connections (the slowest start, if
+    /// the server was started more than once)
+    pub startup: Duration,
+    /// Why the run should not be trusted (for example, because the server crashed)
+    pub invalid: Option<String>,
+    /// The machine and toolchain that the ref was benched on
+    pub environment: Environment,
+    /// The server's resource usage during the measured runs of the primary workload in
+    /// every session (if it was sampled)
+    pub resources: Vec<ResourceUsage>,
+    /// The latencies measured in every run of the primary workload (with the native
+    /// client), or by the idle probe in every session (if it is enabled)
+    pub latencies: Vec<LatencyReport>,
+    /// The client that generated the load
+    pub client: ClientInfo,
+    /// The result of the persistence scenario, if it was run
+    pub persistence: Option<Persistence>,
+}
+
+impl RawResult {
+    /// Returns the report for the primary workload, aggregated across every iteration
+    pub fn report(&self) -> DynResult<Report> {
+        self.workloads[0].report()
+    }
+    /// Returns the report for every cell of the workload matrix. This is empty if only
+    /// the primary workload was run
+    pub fn matrix(&self) -> DynResult<Vec<MatrixCell>> {
+        if self.workloads.len() < 2 {
+            return Ok(Vec::new());
+        }
+        self.workloads
+            .iter()
+            .map(|w| Ok(MatrixCell::new(w.workload, w.report()?)))
+            .collect()
+    }
+    /// Returns the server's resource usage, combined across every session
+    pub fn resources(&self) -> Option<ResourceUsage> {
+        ResourceUsage::combine(&self.resources)
+    }
+    /// Returns the latencies, combined across every run
+    pub fn latency(&self) -> DynResult<Option<LatencyReport>> {
+        LatencyReport::combine(&self.latencies)
+    }
+    /// Returns the delta against `base` for every workload (starting with the primary
+    /// workload)
+    pub fn deltas(&self, base: &RawResult) -> DynResult<Vec<Report>> {
+        self.workloads
+            .iter()
+            .zip(base.workloads.iter())
+            .map(|(this, base)| Ok(this.report()?.delta(&base.report()?)))
+            .collect()
+    }
+    /// Add the runs from another session for the same ref
+    fn merge(&mut self, other: RawResult) {
+        self.workloads
+            .iter_mut()
+            .zip(other.workloads)
+            .for_each(|(this, other)| this.runs.extend(other.runs));
+        self.warmup.extend(other.warmup);
+        self.startup = self.startup.max(other.startup);
+        self.invalid = self.invalid.take().or(other.invalid);
+        self.resources.extend(other.resources);
+        self.latencies.extend(other.latencies);
+    }
+    /// Attach the result of the persistence scenario. If it is invalid (for example,
+    /// because keys were lost), so is the whole run
+    fn set_persistence(&mut self, persistence: Option<Persistence>) {
+        let invalid = persistence
+            .as_ref()
+            .and_then(|p| p.invalid.as_ref())
+            .map(|e| format!("persistence scenario: {}", e));
+        self.invalid = self.invalid.take().or(invalid);
+        self.persistence = persistence;
+    }
+    /// Returns the aggregated report, failing if the run was invalid
+    pub fn valid_report(&self) -> DynResult<Report> {
+        match self.invalid {
+            Some(ref e) => rerr!(format!("The run is invalid: {}", e)),
+            None => self.report(),
+        }
+    }
+}
+
+/// Run the workload against the server with the configured client (`sky-bench` from
+/// `bindir`, or the native client). Only the native client measures the latencies
+fn run_workload(
+    cfg: &Config,
+    bindir: &Path,
+    workload: &Workload,
+) -> DynResult<(Report, Option<LatencyReport>)> {
+    match cfg.bench.client {
+        Client::SkyBench => {
+            let stdout = util::run_benchmark_and_get_stdout(cfg, bindir, workload)?;
+            Ok((Report::from_stdout(stdout)?, None))
+        }
+        Client::Native => {
+            let (report, latency) = loadgen::run(cfg, workload)?;
+            Ok((report, Some(latency)))
+        }
+    }
+}
+
+/// Runs the benchmark until the configured number of warm-up runs are done and the
+/// configured warm-up time has passed, returning the result of every run
+fn warm_up(cfg: &Config, bindir: &Path) -> DynResult<Vec<Report>> {
+    let min_time = Duration::from_secs(cfg.bench.warmup_secs);
+    let start = Instant::now();
+    let mut runs = Vec::with_capacity(cfg.bench.warmup_runs);
+    while runs.len() < cfg.bench.warmup_runs || start.elapsed() < min_time {
+        info!("Warm-up run {}", runs.len() + 1);
+        runs.push(run_workload(cfg, bindir, &cfg.bench.workload())?.0);
+    }
+    if !runs.is_empty() {
+        info!(
+            "Warmed up with {} run(s) in {:?}",
+            runs.len(),
+            start.elapsed()
+        );
+    }
+    Ok(runs)
+}
+
+/// A ref that was checked out and built, and is ready to be benchmarked
+struct Build {
+    commit: String,
+    environment: Environment,
+    mirror: PathBuf,
+    worktree: PathBuf,
+    /// The directory with the binaries
+    bindir: PathBuf,
+}
+
+impl Build {
+    /// Check out `branch` into a worktree at `worktree` and build it (or use the cached
+    /// build). The mirror and the cache are in `basedir`
+    fn prepare(cfg: &Config, basedir: &Path, branch: &str, worktree: PathBuf) -> DynResult<Self> {
+        // first check out the ref from the mirror
+        let mirror = util::mirror_path(cfg, basedir);
+        let commit = watchdog::in_stage(Stage::Checkout, || {
+            let watchdog =
+                Watchdog::new(Stage::Checkout, Duration::from_secs(cfg.timeouts.checkout));
+            util::sync_mirror(cfg, &mirror, &watchdog)?;
+            util::checkout_worktree(&mirror, &worktree, branch, &watchdog)
+        })?;
+        let environment = Environment::capture(&worktree);
+        // build (or use the cached build)
+        let cache = Cache::new(basedir.join(&cfg.cache.dir));
+        let bindir = watchdog::in_stage(Stage::Build, || {
+            cache::build_or_fetch(cfg, &cache, &worktree, &commit)
+        })?;
+        Ok(Self {
+            commit,
+            environment,
+            mirror,
+            worktree,
+            bindir,
+        })
+    }
+    /// Start the server, warm it up and then run the benchmark `iterations` times for
+    /// every workload. `sky-bench` is used from `pinned_client` if it is set (and from
+    /// this build otherwise). The server logs go in `basedir`
+    fn session(
+        &self,
+        cfg: &Config,
+        basedir: &Path,
+        branch: &str,
+        iterations: usize,
+        pinned_client: Option<&Build>,
+    ) -> DynResult<RawResult> {
+        let (client_bindir, client) = match (cfg.bench.client, pinned_client) {
+            (Client::Native, _) => (&self.bindir, None),
+            (Client::SkyBench, Some(build)) => (&build.bindir, Some(build.commit.clone())),
+            (Client::SkyBench, None) => (&self.bindir, Some(self.commit.clone())),
+        };
+        let client = ClientInfo {
+            client: cfg.bench.client,
+            commit: client,
+            pinned: pinned_client.and(cfg.bench.client_ref.clone()),
+        };
+        let environment = Environment {
+            load_average: Environment::load_average(),
+            ..self.environment.clone()
+        };
+        // start the server with a fresh data directory, so that nothing carries over
+        // from an earlier session
+        let datadir = self.fresh_datadir("session")?;
+        let log = util::server_log_path(basedir, branch)?;
+        let (mut server, startup) = watchdog::in_stage(Stage::Startup, || {
+            util::start_server_in_background(cfg, &self.bindir, &datadir, &log)
+        })?;
+        // if the bench fails, the guard will stop the server
+        let (warmup, workloads, latencies, resources) = watchdog::in_stage(Stage::Bench, || {
+            // warm up the server; these runs are not measured
+            let warmup = warm_up(cfg, client_bindir)?;
+            // run the bench for every workload, keeping the latencies and the resource
+            // usage of the primary workload
+            if cfg.server.sample_interval_ms != 0 {
+                server.start_sampling(Duration::from_millis(cfg.server.sample_interval_ms));
+            }
+            let mut workloads = Vec::new();
+            let mut latencies = Vec::new();
+            let mut resources = None;
+            for workload in cfg.bench.workloads() {
+                let mut runs = Vec::with_capacity(iterations);
+                for iteration in 1..=iterations {
+                    info!("Iteration {} of {} ({})", iteration, iterations, workload);
+                    let (report, latency) = run_workload(cfg, client_bindir, &workload)?;
+                    if workloads.is_empty() {
+                        latencies.extend(latency);
+                    }
+                    runs.push(report);
+                }
+                if workloads.is_empty() {
+                    resources = server.finish_sampling();
+                }
+                workloads.push(WorkloadRuns { workload, runs });
+            }
+            // sky-bench doesn't report latencies, so fall back to the idle probe (if
+            // it is enabled)
+            if latencies.is_empty() && cfg.bench.latency_queries != 0 {
+                latencies.push(latency::probe_idle(cfg, cfg.bench.latency_queries)?);
+            }
+            Ok((warmup, workloads, latencies, resources))
+        })?;
+        info!("Stopping server ...");
+        // now stop the server and check that it didn't misbehave
+        let invalid = server.stop()?;
+        remove_datadir(&datadir);
+        Ok(RawResult {
+            commit: self.commit.clone(),
+            workloads,
+            warmup,
+            startup,
+            invalid,
+            environment,
+            resources: resources.into_iter().collect(),
+            latencies,
+            client,
+            persistence: None,
+        })
+    }
+    /// Run the persistence scenario (if it is enabled) with a fresh data directory. The
+    /// server logs go in `basedir`
+    fn persistence(
+        &self,
+        cfg: &Config,
+        basedir: &Path,
+        branch: &str,
+    ) -> DynResult<Option<Persistence>> {
+        if cfg.persistence.keys == 0 {
+            return Ok(None);
+        }
+        let datadir = self.fresh_datadir("persistence")?;
+        let result = persistence::run(cfg, basedir, branch, &self.bindir, &datadir);
+        remove_datadir(&datadir);
+        result.map(Some)
+    }
+    /// Returns an empty data directory for the server, next to the worktree (so that
+    /// it is in the job's workspace)
+    fn fresh_datadir(&self, name: &str) -> DynResult<PathBuf> {
+        let datadir = PathBuf::from(format!("{}-{}-data", self.worktree.display(), name));
+        if datadir.exists() {
+            fs::remove_dir_all(&datadir)?;
+        }
+        fs::create_dir_all(&datadir)?;
+        Ok(datadir)
+    }
+    /// Remove the worktree (and the build files in it)
+    fn remove(self, cfg: &Config) -> DynResult<()> {
+        info!("Removing temporary build files/directories ...");
+        watchdog::in_stage(Stage::Checkout, || {
+            let watchdog =
+                Watchdog::new(Stage::Checkout, Duration::from_secs(cfg.timeouts.checkout));
+            util::remove_worktree(&self.mirror, &self.worktree, &watchdog)
+        })
+    }
+}
+
+/// Remove a server's data directory. The workspace is removed anyway, so a failure
+/// isn't fatal
+fn remove_datadir(datadir: &Path) {
+    if let Err(e) = fs::remove_dir_all(datadir) {
+        warn!("Failed to remove `{}`: {}", datadir.display(), e);
+    }
+}
+
+/// Check out and build the ref that `sky-bench` is pinned to, if it is pinned
+fn prepare_client(cfg: &Config, basedir: &Path, workspace: &Workspace) -> DynResult<Option<Build>> {
+    match (cfg.bench.client, &cfg.bench.client_ref) {
+        (Client::SkyBench, Some(client_ref)) => {
+            info!("Building sky-bench from `{}` ...", client_ref);
+            let worktree = workspace.path(&format!("{}-client", cfg.repo.name));
+            Build::prepare(cfg, basedir, client_ref, worktree).map(Some)
+        }
+        _ => Ok(None),
+    }
 }
 
 /// This returns the raw output from `sky-bench` for the provided `branch`
-pub fn raw_result(branch: &str) -> DynResult<String> {
-    // get the current directory
-    let curdir = cd!();
-    // first clone the release; this will switch to /skytable
-    util::clone_and_checkout(branch)?;
-    // build. this will switch to target/release
-    util::build()?;
-    // start the server
-    let mut child = util::start_server_in_background()?;
-    // run the bench
-    let benchret = util::run_benchmark_and_get_stdout()?;
-    info!("Killing server ...");
-    // now kill server
-    if let Err(e) = child.kill() {
-        error!("Failed to kill server: {}", e);
+pub fn raw_result(cfg: &Config, branch: &str) -> DynResult<RawResult> {
+    let cfg = &cfg.for_job()?;
+    let basedir = env::current_dir()?;
+    let workspace = Workspace::new(cfg)?;
+    let client = prepare_client(cfg, &basedir, &workspace)?;
+    let build = Build::prepare(cfg, &basedir, branch, workspace.path(&cfg.repo.name))?;
+    let mut result = build.session(cfg, &basedir, branch, cfg.bench.iterations, client.as_ref())?;
+    result.set_persistence(build.persistence(cfg, &basedir, branch)?);
+    build.remove(cfg)?;
+    if let Some(client) = client {
+        client.remove(cfg)?;
+    }
+    Ok(result)
+}
+
+/// The output of benchmarking a baseline and a candidate in the same session
+pub struct PairedResult {
+    pub base: RawResult,
+    pub candidate: RawResult,
+}
+
+impl PairedResult {
+    /// Returns the delta of the candidate against the base for every workload (starting
+    /// with the primary workload). Every round's delta is computed from the base and
+    /// candidate runs of that round, and the deltas are then aggregated across rounds
+    pub fn deltas(&self) -> DynResult<Vec<Report>> {
+        self.base
+            .workloads
+            .iter()
+            .zip(self.candidate.workloads.iter())
+            .map(|(base, candidate)| {
+                let deltas = base
+                    .runs
+                    .iter()
+                    .zip(candidate.runs.iter())
+                    .map(|(base, candidate)| candidate.delta(base))
+                    .collect::<Vec<Report>>();
+                Report::from_runs(&deltas)
+            })
+            .collect()
+    }
+}
+
+/// Builds both `base` and `candidate`, and then benchmarks them alternately (base,
+/// candidate, base, candidate, ...) for the configured number of iterations, with every
+/// round getting a freshly started server. This way, both see the same machine state
+pub fn paired_result(cfg: &Config, base: &str, candidate: &str) -> DynResult<PairedResult> {
+    let cfg = &cfg.for_job()?;
+    let basedir = env::current_dir()?;
+    let workspace = Workspace::new(cfg)?;
+    // the same client is used for both
+    let client = prepare_client(cfg, &basedir, &workspace)?;
+    let base_build = Build::prepare(
+        cfg,
+        &basedir,
+        base,
+        workspace.path(&format!("{}-base", cfg.repo.name)),
+    )?;
+    let candidate_build = Build::prepare(
+        cfg,
+        &basedir,
+        candidate,
+        workspace.path(&format!("{}-candidate", cfg.repo.name)),
+    )?;
+    let mut paired: Option<PairedResult> = None;
+    for round in 1..=cfg.bench.iterations {
+        info!("Round {} of {}", round, cfg.bench.iterations);
+        let base_result = base_build.session(cfg, &basedir, base, 1, client.as_ref())?;
+        let candidate_result =
+            candidate_build.session(cfg, &basedir, candidate, 1, client.as_ref())?;
+        paired = Some(match paired {
+            Some(mut paired) => {
+                paired.base.merge(base_result);
+                paired.candidate.merge(candidate_result);
+                paired
+            }
+            None => PairedResult {
+                base: base_result,
+                candidate: candidate_result,
+            },
+        });
+    }
+    // there's always at least one iteration
+    let mut paired = paired.unwrap();
+    let persistence = base_build.persistence(cfg, &basedir, base)?;
+    paired.base.set_persistence(persistence);
+    let persistence = candidate_build.persistence(cfg, &basedir, candidate)?;
+    paired.candidate.set_persistence(persistence);
+    base_build.remove(cfg)?;
+    candidate_build.remove(cfg)?;
+    if let Some(client) = client {
+        client.remove(cfg)?;
+    }
+    Ok(paired)
+}
+
+#[cfg(test)]
+fn test_result(stats: &[f32]) -> RawResult {
+    RawResult {
+        commit: "abcd".to_owned(),
+        workloads: vec![WorkloadRuns {
+            workload: Config::default().bench.workload(),
+            runs: stats
+                .iter()
+                .map(|stat| Report::new(*stat, *stat, *stat))
+                .collect(),
+        }],
+        warmup: Vec::new(),
+        startup: Duration::from_millis(10),
+        invalid: None,
+        environment: Environment::default(),
+        resources: Vec::new(),
+        latencies: Vec::new(),
+        client: ClientInfo {
+            client: Client::SkyBench,
+            commit: Some("abcd".to_owned()),
+            pinned: None,
+        },
+        persistence: None,
+    }
+}
+
+#[test]
+fn test_paired_deltas() {
+    use crate::args::Metric;
+    let result = test_result;
+    // the machine got slower in the second round; the candidate is 10% and then 20%
+    // faster than the base in the same round
+    let mut paired = PairedResult {
+        base: result(&[100.0]),
+        candidate: result(&[110.0]),
     };
-    info!("Switching to the base directory ...");
-    // now switch to the original dir
-    cd!(curdir);
-    // clean up after build is over ($PWD/skytable)
-    info!("Removing temporary build files/directories ...");
-    fs::remove_dir_all("skytable")?;
-    Ok(benchret)
+    paired.base.merge(result(&[50.0]));
+    paired.candidate.merge(result(&[60.0]));
+    let deltas = paired.deltas().unwrap();
+    assert!((deltas[0].metric(Metric::Get) - 15.0).abs() < 1e-3);
+    // comparing the aggregates instead weighs the faster round more
+    let unpaired = paired.candidate.deltas(&paired.base).unwrap();
+    assert!((unpaired[0].metric(Metric::Get) - 13.333).abs() < 1e-3);
+}
+
+#[test]
+fn test_client_difference() {
+    let sky_bench = |commit: &str, pinned: Option<&str>| ClientInfo {
+        client: Client::SkyBench,
+        commit: Some(commit.to_owned()),
+        pinned: pinned.map(str::to_owned),
+    };
+    let native = ClientInfo {
+        client: Client::Native,
+        commit: None,
+        pinned: None,
+    };
+    // sky-bench changes with the ref anyway
+    assert!(sky_bench("abcd", None)
+        .difference(&sky_bench("efgh", None))
+        .is_none());
+    let pinned = sky_bench("abcd", Some("v0.7.5"));
+    assert!(pinned.difference(&sky_bench("abcd", None)).is_none());
+    assert_eq!(
+        pinned.difference(&sky_bench("efgh", None)).unwrap(),
+        "sky-bench, pinned to `v0.7.5` (abcd) v/s sky-bench, built from the benchmarked commit (efgh)"
+    );
+    assert!(native.difference(&native).is_none());
+    assert!(native.difference(&pinned).is_some());
+}
+
+#[test]
+fn test_invalid_persistence() {
+    let persistence = |invalid: Option<&str>| Persistence {
+        keys: 1000,
+        size: 64,
+        snapshot_ms: Some(40),
+        shutdown_ms: 20,
+        disk_bytes: 1024,
+        recovery_ms: 150,
+        recovered_keys: Some(500),
+        invalid: invalid.map(str::to_owned),
+    };
+    let mut result = test_result(&[100.0]);
+    result.set_persistence(Some(persistence(None)));
+    assert!(result.valid_report().is_ok());
+    result.set_persistence(Some(persistence(Some(
+        "only 500 of 1000 keys were recovered",
+    ))));
+    assert_eq!(
+        result.invalid.as_deref(),
+        Some("persistence scenario: only 500 of 1000 keys were recovered")
+    );
+    assert!(result.valid_report().is_err());
+    assert!(ReportItem::from_result("abcd".to_owned(), &result).is_err());
+    // a problem with the bench itself comes first
+    let mut result = test_result(&[100.0]);
+    result.invalid = Some("the server panicked".to_owned());
+    result.set_persistence(Some(persistence(Some("keys were lost"))));
+    assert_eq!(result.invalid.as_deref(), Some("the server panicked"));
+}
+
+#[cfg(test)]
+/// Returns a build in `root` with a fake `skyd` (that only waits to be stopped; the
+/// queries go to a fake Skyhash server) and a fake `sky-bench`, whose `n`th run reports
+/// `100 * n` queries per second for every operation
+fn fake_build(root: &Path) -> Build {
+    use std::os::unix::fs::PermissionsExt;
+    let bindir = root.join("bin");
+    fs::create_dir_all(&bindir).unwrap();
+    let scripts = [
+        ("skyd", "#!/bin/sh\nexec sleep 30\n"),
+        (
+            "sky-bench",
+            r#"#!/bin/sh
+echo >> runs
+stat=$(( $(wc -l < runs) * 100 ))
+echo "[{\"name\":\"GET\",\"stat\":$stat},{\"name\":\"SET\",\"stat\":$stat},{\"name\":\"UPDATE\",\"stat\":$stat}]"
+"#,
+        ),
+    ];
+    for (name, script) in scripts.iter() {
+        fs::write(bindir.join(name), script).unwrap();
+        fs::set_permissions(bindir.join(name), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    Build {
+        commit: "abcd".to_owned(),
+        environment: Environment::default(),
+        mirror: root.join("mirror"),
+        worktree: root.join("skytable"),
+        bindir,
+    }
+}
+
+#[test]
+fn test_default_session_latency() {
+    use crate::args::Metric;
+    use crate::latency::LatencyMode;
+    use crate::skyhash;
+    let root = env::temp_dir().join(format!("skyreport-session-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let build = fake_build(&root);
+    let mut cfg = Config::default();
+    cfg.server.port = skyhash::fake::start(|action| match action {
+        "GET" => b"*1\n+1\nx\n",
+        _ => b"*1\n!1\n0\n",
+    });
+    let result = build.session(&cfg, &root, "next", 1, None).unwrap();
+    // sky-bench can't measure the latencies, so the idle probe does
+    let latency = result.latency().unwrap().unwrap();
+    assert_eq!(latency.mode, LatencyMode::IdleProbe);
+    assert_eq!(
+        latency.metric(Metric::Get).count,
+        cfg.bench.latency_queries as u64
+    );
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_warm_up() {
+    use crate::args::Metric;
+    let root = env::temp_dir().join(format!("skyreport-warmup-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let build = fake_build(&root);
+    let mut cfg = Config::default();
+    // no warm-up by default
+    assert!(warm_up(&cfg, &build.bindir).unwrap().is_empty());
+    // stop after the configured number of runs
+    cfg.bench.warmup_runs = 2;
+    let runs = warm_up(&cfg, &build.bindir).unwrap();
+    let stats: Vec<f32> = runs.iter().map(|r| r.metric(Metric::Get)).collect();
+    assert_eq!(stats, [100.0, 200.0]);
+    // keep going until the minimum time has passed, even without a run count
+    cfg.bench.warmup_runs = 0;
+    cfg.bench.warmup_secs = 1;
+    let start = Instant::now();
+    let runs = warm_up(&cfg, &build.bindir).unwrap();
+    assert!(!runs.is_empty());
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_session_warmup() {
+    use crate::args::Metric;
+    let root = env::temp_dir().join(format!("skyreport-warmup-session-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let build = fake_build(&root);
+    let mut cfg = Config::default();
+    cfg.server.port = crate::skyhash::fake::start(|_| b"*1\n!1\n0\n");
+    cfg.bench.warmup_runs = 2;
+    cfg.bench.latency_queries = 0;
+    let result = build.session(&cfg, &root, "next", 2, None).unwrap();
+    // the first two runs are the warm-up runs, and are kept apart
+    let warmup: Vec<f32> = result
+        .warmup
+        .iter()
+        .map(|r| r.metric(Metric::Get))
+        .collect();
+    assert_eq!(warmup, [100.0, 200.0]);
+    assert_eq!(result.workloads[0].runs.len(), 2);
+    // so only the measured runs (300 and 400) count towards the report and the deltas
+    assert_eq!(result.report().unwrap().metric(Metric::Get), 350.0);
+    let deltas = result.deltas(&test_result(&[350.0])).unwrap();
+    assert_eq!(deltas[0].metric(Metric::Get), 0.0);
+    let item = ReportItem::from_result(result.commit.clone(), &result).unwrap();
+    assert_eq!(item.report.metric(Metric::Get), 350.0);
+    fs::remove_dir_all(&root).unwrap();
 }
@@ -22,88 +22,384 @@
  *
 */
 
+use crate::affinity;
+use crate::config::{Config, ServerConfig, Workload};
+use crate::server::ServerGuard;
+use crate::watchdog::{Stage, StageError, Watchdog};
 use std::env;
 use std::fs;
-use std::io::Write;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::process::Child;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 pub type DynResult<T> = Result<T, Box<dyn std::error::Error>>;
-pub const REPO_URL: &str = "https://github.com/skytable/skytable.git";
-pub const ORG_NAME: &str = "skytable";
-pub const REPO_NAME: &str = "skytable";
-pub const REPO_PERF: &str = "perf.git";
-pub const RELEASE_DIR: &str = "target/release";
-pub const VAR_LATEST_COMMIT: &str = "LATEST_COMMIT";
+pub const DIR_PRESET: &str = "preset";
+pub const DIR_RESULTS: &str = "results";
+pub const DIR_REPORTS: &str = "reports";
+/// The server logs for every run are kept here
+pub const DIR_LOGS: &str = "logs";
 pub const VAR_ACTION_RUN_ID: &str = "GITHUB_RUN_ID";
 
-pub fn get_latest_commit() -> DynResult<String> {
-    Ok(env::var(VAR_LATEST_COMMIT).map(|v| v.to_string().replace('"', ""))?)
-}
-
-/// This will clone the skytable/skytable repo, switch to the directory
-/// and checkout the branch, returning errors if any do occur
-pub fn clone_and_checkout(branch: &str) -> DynResult<()> {
-    info!("Cloning repo ...");
-    hspawnerr!("git", "clone", REPO_URL);
-    info!("Switching to repo directory ...");
-    env::set_current_dir(REPO_NAME)?;
-    info!("Checking out branch `{}`", branch);
-    hspawnerr!("git", "checkout", branch);
-    // now set the latest commit
-    info!("Setting `{}` to the latest SHA on HEAD", VAR_LATEST_COMMIT);
-    let latest_commit = cmd!("git", "log", "-n", "1", "--pretty=format:\"%H\"").output()?;
-    if !latest_commit.stderr.is_empty() {
-        return rerr!("Failed to get the commit hash");
+/// Used to give every workspace in this process a unique name
+static WORKSPACE_ID: AtomicUsize = AtomicUsize::new(0);
+/// Used to give every server log in this process a unique name
+static LOG_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary directory for the worktrees (and builds) of a single job. It is removed
+/// (along with everything in it) when dropped
+pub struct Workspace {
+    root: PathBuf,
+}
+
+impl Workspace {
+    /// Create a new workspace in the configured directory (or the system's temporary
+    /// directory, if there isn't one)
+    pub fn new(cfg: &Config) -> DynResult<Self> {
+        let parent = match cfg.bench.workspace {
+            Some(ref dir) => env::current_dir()?.join(dir),
+            None => env::temp_dir(),
+        };
+        let root = parent.join(format!(
+            "skyreport-{}-{}",
+            process::id(),
+            WORKSPACE_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&root)?;
+        info!("Using workspace `{}`", root.display());
+        Ok(Self { root })
+    }
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.root) {
+            warn!(
+                "Failed to remove workspace `{}`: {}",
+                self.root.display(),
+                e
+            );
+        }
+    }
+}
+
+/// An exclusive lock (with `flock`) on a file, which keeps other jobs (in this process
+/// or any other) out of a shared directory. It is released when dropped
+pub struct FileLock {
+    _file: fs::File,
+}
+
+impl FileLock {
+    /// Wait for the lock on `path`, creating the file if it doesn't exist
+    pub fn acquire(path: &Path) -> DynResult<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        // every open file is locked separately, so this works across threads too
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return rerr!(format!(
+                "Failed to lock `{}`: {}",
+                path.display(),
+                io::Error::last_os_error()
+            ));
+        }
+        Ok(Self { _file: file })
+    }
+}
+
+/// Returns the path of the lock for a shared directory (next to it, so that it isn't
+/// mistaken for the directory's contents)
+pub fn lock_path(dir: &Path) -> PathBuf {
+    let mut path = dir.as_os_str().to_owned();
+    path.push(".lock");
+    PathBuf::from(path)
+}
+
+/// Returns the SHA of the latest commit on `branch` in the configured repo
+pub fn get_remote_head(cfg: &Config, branch: &str, watchdog: &Watchdog) -> DynResult<String> {
+    let refname = format!("refs/heads/{}", branch);
+    let output = watchdog.output(&mut cmd!(
+        "git",
+        "ls-remote",
+        cfg.repo.clone_url(),
+        &refname
+    ))?;
+    if !output.status.success() {
+        return rerr!(format!(
+            "Failed to get the remote head for `{}`: `{}`",
+            branch,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // the output looks like: `<sha>\trefs/heads/<branch>`
+    match stdout.split_whitespace().next() {
+        Some(sha) => Ok(sha.to_owned()),
+        None => rerr!(format!("No such branch: `{}`", branch)),
+    }
+}
+
+/// Returns the path of the bare mirror of the configured repo, under `basedir`
+pub fn mirror_path(cfg: &Config, basedir: &Path) -> PathBuf {
+    basedir
+        .join(&cfg.repo.mirror)
+        .join(format!("{}.git", cfg.repo.name))
+}
+
+/// This will create the mirror of the configured repo if it doesn't exist yet, and
+/// fetch the latest changes into it otherwise
+pub fn sync_mirror(cfg: &Config, mirror: &Path, watchdog: &Watchdog) -> DynResult<()> {
+    let url = cfg.repo.clone_url();
+    // concurrent jobs share the mirror
+    let _lock = FileLock::acquire(&lock_path(mirror))?;
+    if mirror.exists() {
+        info!("Fetching into the mirror at `{}` ...", mirror.display());
+        // the URL may have been changed in the configuration
+        watchdog.run(&mut cmd!(
+            "git", "-C", mirror, "remote", "set-url", "origin", &url
+        ))?;
+        watchdog.run(&mut cmd!("git", "-C", mirror, "fetch", "--prune", "origin"))?;
+    } else {
+        info!("Creating a mirror at `{}` ...", mirror.display());
+        watchdog.run(&mut cmd!("git", "clone", "--mirror", &url, mirror))?;
     }
-    env::set_var(
-        VAR_LATEST_COMMIT,
-        String::from_utf8_lossy(&latest_commit.stdout)
-            .to_string()
-            .replace('"', ""),
-    );
-    info!("Done cloning. Returning control ...");
     Ok(())
 }
 
-/// This will prepare a release build for skytable and switch to the `target/release`
-/// directory.
-///
-/// **Important note:** This function expects to be in the source directory
-pub fn build() -> DynResult<()> {
+/// This will check out `branch` from the mirror into a new worktree at `path`, returning
+/// the commit that was checked out
+pub fn checkout_worktree(
+    mirror: &Path,
+    path: &Path,
+    branch: &str,
+    watchdog: &Watchdog,
+) -> DynResult<String> {
+    if path.exists() {
+        // this is left over from a run that failed
+        warn!("Removing stale worktree at `{}`", path.display());
+        remove_worktree(mirror, path, watchdog)?;
+    }
+    info!("Checking out `{}` into `{}`", branch, path.display());
+    watchdog.run(&mut cmd!(
+        "git", "-C", mirror, "worktree", "add", "--detach", path, branch
+    ))?;
+    let commit = watchdog.output(&mut cmd!("git", "-C", path, "rev-parse", "HEAD"))?;
+    if !commit.status.success() {
+        return rerr!("Failed to get the commit hash");
+    }
+    let commit = String::from_utf8_lossy(&commit.stdout).trim().to_owned();
+    info!("Checked out `{}` at {}", branch, commit);
+    Ok(commit)
+}
+
+/// This will remove the worktree at `path` (including any build artifacts)
+pub fn remove_worktree(mirror: &Path, path: &Path, watchdog: &Watchdog) -> DynResult<()> {
+    let removed = watchdog.output(&mut cmd!(
+        "git", "-C", mirror, "worktree", "remove", "--force", path
+    ))?;
+    if !removed.status.success() {
+        // not a worktree that git knows about (for example, an old clone)
+        fs::remove_dir_all(path)?;
+    }
+    watchdog.run(&mut cmd!("git", "-C", mirror, "worktree", "prune"))
+}
+
+/// This will prepare a release build for the source tree in `srcdir`, returning the
+/// directory with the binaries
+pub fn build(cfg: &Config, srcdir: &Path) -> DynResult<PathBuf> {
     info!("Starting build ... (this may take a while)");
-    hspawnerr!(
-        "cargo",
-        "build",
-        "-p",
-        "skyd",
-        "-p",
-        "sky-bench",
-        "--release"
-    );
-    info!("Switching to the release directory ... ");
-    env::set_current_dir(RELEASE_DIR)?;
+    let watchdog = Watchdog::new(Stage::Build, Duration::from_secs(cfg.timeouts.build));
+    watchdog.run(
+        cmd!(
+            "cargo",
+            "build",
+            "-p",
+            "skyd",
+            "-p",
+            "sky-bench",
+            "--release"
+        )
+        .current_dir(srcdir),
+    )?;
     info!("Done building. Returning control ...");
-    Ok(())
+    Ok(srcdir.join(&cfg.bench.release_dir))
+}
+
+/// How often we check if the server is ready
+const PROBE_INTERVAL: Duration = Duration::from_millis(100);
+/// How long a single connection attempt (or heartbeat) can take
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+/// A Skyhash 1.0 query with a single `HEYA` action
+const SKYHASH_HEYA: &[u8] = b"*1\n~1\n4\nHEYA\n";
+const SKYHASH_HEY: &[u8] = b"HEY!";
+
+/// Returns the path of a new log file for a run of the server for `branch`, in the
+/// logs directory under `basedir`
+pub fn server_log_path(basedir: &Path, branch: &str) -> DynResult<PathBuf> {
+    let dir = basedir.join(DIR_LOGS);
+    fs::create_dir_all(&dir)?;
+    let branch: String = branch
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    // the process and a counter tell apart the servers started in the same second
+    Ok(dir.join(format!(
+        "skyd-{}-{}-{}-{}.log",
+        branch,
+        now,
+        process::id(),
+        LOG_ID.fetch_add(1, Ordering::Relaxed)
+    )))
+}
+
+/// This will start the server in `bindir` as a child process (with its output going to
+/// `log`) and wait until it is ready, returning the server and the time it took to
+/// start up. The server runs in (and stores its data in) `workdir`
+pub fn start_server_in_background(
+    cfg: &Config,
+    bindir: &Path,
+    workdir: &Path,
+    log: &Path,
+) -> DynResult<(ServerGuard, Duration)> {
+    info!(
+        "Starting server in background (logging to `{}`)",
+        log.display()
+    );
+    let mut cmd = cmd!(
+        bindir.join("skyd"),
+        "--noart",
+        "--host",
+        &cfg.server.host,
+        "--port",
+        cfg.server.port.to_string()
+    );
+    cmd.current_dir(workdir);
+    if let Some(cpus) = affinity::server_cpus(cfg)? {
+        info!("Pinning the server to CPUs `{}`", cpus);
+        cpus.apply(&mut cmd);
+    }
+    let start = Instant::now();
+    let mut server =
+        ServerGuard::spawn(cmd, log, Duration::from_secs(cfg.server.shutdown_timeout))?;
+    info!("Waiting for server to start up");
+    // if this fails, the guard will stop the server
+    wait_for_server(&cfg.server, server.child_mut(), start)?;
+    let startup = start.elapsed();
+    info!("Server started up in {:?}. Returning control ...", startup);
+    Ok((server, startup))
+}
+
+/// Returns the total size of the files in `path` (and every directory in it)
+pub fn dir_size(path: &Path) -> DynResult<u64> {
+    let mut size = 0;
+    for dirent in fs::read_dir(path)? {
+        let dirent = dirent?;
+        let metadata = dirent.metadata()?;
+        size += match metadata.is_dir() {
+            true => dir_size(&dirent.path())?,
+            false => metadata.len(),
+        };
+    }
+    Ok(size)
+}
+
+/// Returns the address that the server listens on
+pub fn server_addr(server: &ServerConfig) -> DynResult<SocketAddr> {
+    match (server.host.as_str(), server.port)
+        .to_socket_addrs()?
+        .next()
+    {
+        Some(addr) => Ok(addr),
+        None => rerr!(format!("Failed to resolve `{}`", server.host)),
+    }
+}
+
+/// Polls the server until it is ready, failing if it exits or takes too long
+fn wait_for_server(server: &ServerConfig, child: &mut Child, start: Instant) -> DynResult<()> {
+    let addr = server_addr(server)?;
+    let timeout = Duration::from_secs(server.startup_timeout);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return rerr!(format!("The server exited during startup ({})", status));
+        }
+        if probe_server(addr, server.heartbeat) {
+            return Ok(());
+        }
+        if start.elapsed() > timeout {
+            error!(
+                "The server didn't start listening on {} within {}s",
+                addr, server.startup_timeout
+            );
+            return Err(StageError::timed_out(Stage::Startup, timeout).into());
+        }
+        thread::sleep(PROBE_INTERVAL);
+    }
 }
 
-/// This will start the server as a child process (sharing same stdout/stderr)
-///
-/// **Important note:** This function expects to be in the `target/release` directory
-pub fn start_server_in_background() -> DynResult<Child> {
-    info!("Starting server in background");
-    let child = cmd!("./skyd", "--noart").spawn()?;
-    info!("Sleeping to wait for server to start up");
-    sleep!(10);
-    info!("Finished sleeping. Returning control ...");
-    Ok(child)
-}
-
-/// This will run the benchmark with the defaults for `skyreport` and return the stdout
-///
-/// **Important note:** This function expects to be in the `target/release` directory
-pub fn run_benchmark_and_get_stdout() -> DynResult<String> {
-    info!("Beginning benchmark ...");
-    let output = cmd!("./sky-bench", "-c50", "-q1000000", "-s4", "--json").output()?;
+/// Returns true if the server at `addr` is accepting connections (and replies to
+/// a heartbeat, if `heartbeat` is set)
+fn probe_server(addr: SocketAddr, heartbeat: bool) -> bool {
+    let mut con = match TcpStream::connect_timeout(&addr, PROBE_TIMEOUT) {
+        Ok(con) => con,
+        Err(_) => return false,
+    };
+    if !heartbeat {
+        return true;
+    }
+    let mut send_heya = || -> std::io::Result<bool> {
+        con.set_read_timeout(Some(PROBE_TIMEOUT))?;
+        con.write_all(SKYHASH_HEYA)?;
+        let mut response = Vec::new();
+        let mut buf = [0u8; 64];
+        loop {
+            let read = con.read(&mut buf)?;
+            if read == 0 {
+                return Ok(false);
+            }
+            response.extend_from_slice(&buf[..read]);
+            if response
+                .windows(SKYHASH_HEY.len())
+                .any(|w| w == SKYHASH_HEY)
+            {
+                return Ok(true);
+            }
+        }
+    };
+    send_heya().unwrap_or(false)
+}
+
+/// This will run the benchmark in `bindir` with the provided workload and return the
+/// stdout
+pub fn run_benchmark_and_get_stdout(
+    cfg: &Config,
+    bindir: &Path,
+    workload: &Workload,
+) -> DynResult<String> {
+    info!("Beginning benchmark ({}) ...", workload);
+    let mut cmd = std::process::Command::new(bindir.join("sky-bench"));
+    cmd.args(workload.args())
+        .args(["--host", &cfg.server.host])
+        .args(["--port", &cfg.server.port.to_string()])
+        .current_dir(bindir);
+    if let Some(cpus) = affinity::client_cpus(cfg)? {
+        cpus.apply(&mut cmd);
+    }
+    let watchdog = Watchdog::new(Stage::Bench, Duration::from_secs(cfg.timeouts.bench));
+    let output = watchdog.output(&mut cmd)?;
     let stderr = &output.stderr;
     if !stderr.is_empty() {
         return rerr!(format!(
@@ -130,3 +426,148 @@ pub fn create_and_write_to_file(fname: &str, body: &[u8]) -> DynResult<()> {
     file.write_all(body)?;
     Ok(())
 }
+
+#[test]
+fn test_probe_server() {
+    use std::net::TcpListener;
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    assert!(probe_server(listener.local_addr().unwrap(), false));
+    drop(listener);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let responder = thread::spawn(move || {
+        let (mut con, _) = listener.accept().unwrap();
+        let mut buf = [0u8; SKYHASH_HEYA.len()];
+        con.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, SKYHASH_HEYA);
+        con.write_all(b"*1\n+4\nHEY!\n").unwrap();
+    });
+    assert!(probe_server(addr, true));
+    responder.join().unwrap();
+}
+
+#[test]
+fn test_wait_for_server_child_exits() {
+    let server = ServerConfig {
+        port: 1,
+        ..ServerConfig::default()
+    };
+    let mut child = cmd!("true",).spawn().unwrap();
+    let err = wait_for_server(&server, &mut child, Instant::now()).unwrap_err();
+    assert!(err.to_string().contains("exited during startup"));
+}
+
+#[test]
+fn test_sync_mirror() {
+    let root = env::temp_dir().join(format!("skyreport-mirror-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let source = root.join("source");
+    fs::create_dir_all(&source).unwrap();
+    let git = |args: &[&str]| {
+        let status = cmd!(
+            "git",
+            "-C",
+            &source,
+            "-c",
+            "user.name=perf",
+            "-c",
+            "user.email=perf@localhost"
+        )
+        .args(args)
+        .status()
+        .unwrap();
+        assert!(status.success());
+    };
+    git(&["init", "-q"]);
+    git(&["commit", "-q", "--allow-empty", "-m", "first"]);
+    let mut cfg = Config::default();
+    cfg.repo.url = Some(source.to_string_lossy().into_owned());
+    let mirror = mirror_path(&cfg, &root);
+    assert_eq!(mirror, root.join("mirror").join("skytable.git"));
+    let watchdog = Watchdog::new(Stage::Checkout, Duration::from_secs(60));
+    sync_mirror(&cfg, &mirror, &watchdog).unwrap();
+    // new commits are fetched into the existing mirror
+    git(&["tag", "v0.1.0"]);
+    sync_mirror(&cfg, &mirror, &watchdog).unwrap();
+    let tag = cmd!(
+        "git",
+        "-C",
+        &mirror,
+        "rev-parse",
+        "--verify",
+        "-q",
+        "v0.1.0"
+    )
+    .status()
+    .unwrap();
+    assert!(tag.success());
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_workspace() {
+    let cfg = Config::default();
+    let (first, second) = (Workspace::new(&cfg).unwrap(), Workspace::new(&cfg).unwrap());
+    // concurrent jobs get their own workspaces
+    assert_ne!(first.path("skytable"), second.path("skytable"));
+    fs::create_dir_all(first.path("skytable").join("target")).unwrap();
+    let root = first.root.clone();
+    drop(first);
+    assert!(!root.exists());
+}
+
+#[test]
+fn test_dir_size() {
+    let root = env::temp_dir().join(format!("skyreport-size-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let nested = root.join("a").join("b");
+    fs::create_dir_all(&nested).unwrap();
+    fs::create_dir_all(root.join("empty")).unwrap();
+    assert_eq!(dir_size(&root).unwrap(), 0);
+    fs::write(root.join("top"), [0u8; 3]).unwrap();
+    fs::write(root.join("a").join("middle"), [0u8; 20]).unwrap();
+    fs::write(nested.join("bottom"), [0u8; 100]).unwrap();
+    fs::write(nested.join("other"), [0u8; 1000]).unwrap();
+    // every level is counted, and only the files count
+    assert_eq!(dir_size(&root).unwrap(), 1123);
+    assert_eq!(dir_size(&root.join("a")).unwrap(), 1120);
+    assert!(dir_size(&root.join("missing")).is_err());
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_file_lock() {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    let root = env::temp_dir().join(format!("skyreport-lock-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let path = lock_path(&root.join("mirror"));
+    assert_eq!(path, root.join("mirror.lock"));
+    let lock = FileLock::acquire(&path).unwrap();
+    let acquired = Arc::new(AtomicBool::new(false));
+    let waiter = {
+        let (path, acquired) = (path.clone(), acquired.clone());
+        thread::spawn(move || {
+            let _lock = FileLock::acquire(&path).unwrap();
+            acquired.store(true, Ordering::SeqCst);
+        })
+    };
+    thread::sleep(Duration::from_millis(200));
+    assert!(!acquired.load(Ordering::SeqCst));
+    drop(lock);
+    waiter.join().unwrap();
+    assert!(acquired.load(Ordering::SeqCst));
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_server_log_path() {
+    let root = env::temp_dir().join(format!("skyreport-logs-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    // servers started in the same second get their own logs
+    let first = server_log_path(&root, "next").unwrap();
+    let second = server_log_path(&root, "next").unwrap();
+    assert_ne!(first, second);
+    assert!(first.starts_with(root.join(DIR_LOGS)));
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,406 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::args::ValidateOptions;
+use crate::bencher::render_list;
+use crate::bencher::RawReport;
+use crate::history::Timestamp;
+use crate::updater::ReportItem;
+use crate::util;
+use crate::DynResult;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files that can't be repaired are moved here (when asked to)
+const DIR_QUARANTINE: &str = "quarantine";
+const REPORT_HEADING: &str = "# Skyreport";
+
+#[derive(Debug, PartialEq)]
+enum Problem {
+    /// The file name doesn't follow the `result-DDMMYYYY-HHMMSS` convention
+    BadName,
+    /// The file name has stray whitespace and can be renamed to the provided name
+    Renamable(String),
+    /// The file couldn't be read or decoded
+    Corrupt(String),
+    /// The result doesn't have a report or the report doesn't have a result
+    Orphan,
+    /// The result and the report don't agree
+    Mismatch(String),
+    /// The file couldn't be repaired
+    RepairFailed(String),
+}
+
+#[derive(Debug)]
+struct Issue {
+    path: PathBuf,
+    problem: Problem,
+}
+
+impl Issue {
+    fn new(path: &Path, problem: Problem) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            problem,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.display().to_string();
+        let path = path.escape_debug();
+        match self.problem {
+            Problem::BadName => write!(f, "`{}`: bad file name", path),
+            Problem::Renamable(ref name) => {
+                write!(
+                    f,
+                    "`{}`: bad file name (can be renamed to `{}`)",
+                    path, name
+                )
+            }
+            Problem::Corrupt(ref e) => write!(f, "`{}`: {}", path, e),
+            Problem::Orphan => write!(f, "`{}`: no matching result or report", path),
+            Problem::Mismatch(ref e) => write!(f, "`{}`: {}", path, e),
+            Problem::RepairFailed(ref e) => write!(f, "`{}`: failed to repair: {}", path, e),
+        }
+    }
+}
+
+/// Returns the name with stray whitespace removed, if that makes it a valid name
+fn fixed_name(name: &str) -> Option<String> {
+    let fixed: String = name
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    if fixed != name && Timestamp::from_filename(&fixed).is_some() {
+        Some(fixed)
+    } else {
+        None
+    }
+}
+
+/// Lists the files in `dir`, along with their names
+fn list_dir(dir: &Path) -> DynResult<Vec<(PathBuf, String)>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+    for file in fs::read_dir(dir)? {
+        let path = file?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let name = name.into_owned();
+        files.push((path, name));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Checks the name of a result or report file, returning the name that it should have
+/// (if it can be salvaged at all)
+fn check_name(path: &Path, name: &str, ext: &str, issues: &mut Vec<Issue>) -> Option<String> {
+    if name.ends_with(ext) && Timestamp::from_filename(name).is_some() {
+        return Some(name.to_owned());
+    }
+    match fixed_name(name).filter(|fixed| fixed.ends_with(ext)) {
+        Some(fixed) => {
+            issues.push(Issue::new(path, Problem::Renamable(fixed.clone())));
+            Some(fixed)
+        }
+        None => {
+            issues.push(Issue::new(path, Problem::BadName));
+            None
+        }
+    }
+}
+
+/// Walks the store in `root` and returns every problem found
+fn scan(root: &Path) -> DynResult<Vec<Issue>> {
+    let mut issues = Vec::new();
+    // presets
+    for (path, _) in list_dir(&root.join(util::DIR_PRESET))? {
+        let item = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|body| serde_json::from_str::<ReportItem>(&body).map_err(|e| e.to_string()));
+        if let Err(e) = item {
+            issues.push(Issue::new(&path, Problem::Corrupt(e)));
+        }
+    }
+    // results (keyed by the timestamp, which is what pairs them with reports)
+    let mut results = BTreeMap::new();
+    for (path, name) in list_dir(&root.join(util::DIR_RESULTS))? {
+        let name = match check_name(&path, &name, ".json", &mut issues) {
+            Some(name) => name,
+            None => continue,
+        };
+        let report = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|body| serde_json::from_str::<RawReport>(&body).map_err(|e| e.to_string()));
+        match report {
+            Ok(report) => {
+                results.insert(Timestamp::from_filename(&name), (path, report));
+            }
+            Err(e) => issues.push(Issue::new(&path, Problem::Corrupt(e))),
+        }
+    }
+    // reports
+    let mut reports = BTreeMap::new();
+    for (path, name) in list_dir(&root.join(util::DIR_REPORTS))? {
+        let name = match check_name(&path, &name, ".md", &mut issues) {
+            Some(name) => name,
+            None => continue,
+        };
+        match fs::read_to_string(&path) {
+            Ok(body) if body.starts_with(REPORT_HEADING) => {
+                reports.insert(Timestamp::from_filename(&name), (path, body));
+            }
+            Ok(_) => issues.push(Issue::new(
+                &path,
+                Problem::Corrupt("not a skyreport report".to_owned()),
+            )),
+            Err(e) => issues.push(Issue::new(&path, Problem::Corrupt(e.to_string()))),
+        }
+    }
+    // now cross-reference them
+    for (timestamp, (path, result)) in results.iter() {
+        let body = match reports.get(timestamp) {
+            Some((_, body)) => body,
+            None => {
+                issues.push(Issue::new(path, Problem::Orphan));
+                continue;
+            }
+        };
+        if let Some(ref pr) = result.pr {
+            if !body.contains(&format!("Pull request: [{}]", pr)) {
+                issues.push(Issue::new(
+                    path,
+                    Problem::Mismatch(format!("the report isn't for PR#{}", pr)),
+                ));
+            }
+        }
+    }
+    for (timestamp, (path, _)) in reports.iter() {
+        if !results.contains_key(timestamp) {
+            issues.push(Issue::new(path, Problem::Orphan));
+        }
+    }
+    Ok(issues)
+}
+
+#[derive(Debug, PartialEq)]
+/// What was done to repair a file
+enum Repair {
+    /// The file was left as it is
+    None,
+    /// The file was renamed, which resolves its name (but nothing else)
+    Renamed,
+    /// The file was moved out of the way, which resolves all of its issues
+    Quarantined,
+}
+
+/// Attempts to resolve the issues with the file at `path`. A file is only repaired
+/// once: if it has to be quarantined, it isn't renamed
+fn resolve(
+    root: &Path,
+    path: &Path,
+    issues: &[Issue],
+    opts: &ValidateOptions,
+) -> DynResult<Repair> {
+    let unusable = issues
+        .iter()
+        .find(|issue| matches!(issue.problem, Problem::BadName | Problem::Corrupt(_)));
+    if let Some(issue) = unusable.filter(|_| opts.quarantine) {
+        let parent = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .unwrap_or_default();
+        let dir = root.join(DIR_QUARANTINE).join(parent);
+        fs::create_dir_all(&dir)?;
+        info!("Quarantining {}", issue);
+        fs::rename(path, dir.join(path.file_name().unwrap_or_default()))?;
+        return Ok(Repair::Quarantined);
+    }
+    let renamable = issues.iter().find_map(|issue| match issue.problem {
+        Problem::Renamable(ref name) if opts.fix => Some((issue, name)),
+        _ => None,
+    });
+    if let Some((issue, name)) = renamable {
+        let target = path.with_file_name(name);
+        if target.exists() {
+            warn!("Not renaming: `{}` already exists", target.display());
+            return Ok(Repair::None);
+        }
+        info!("Renaming {}", issue);
+        fs::rename(path, target)?;
+        return Ok(Repair::Renamed);
+    }
+    Ok(Repair::None)
+}
+
+/// Validate the store in `root`, returning the problems that remain. A file that can't
+/// be repaired doesn't stop the others from being repaired
+fn validate(root: &Path, opts: &ValidateOptions) -> DynResult<Vec<Issue>> {
+    // a file can have several issues (like a bad name and a corrupt body)
+    let mut files: BTreeMap<PathBuf, Vec<Issue>> = BTreeMap::new();
+    for issue in scan(root)? {
+        files.entry(issue.path.clone()).or_default().push(issue);
+    }
+    let mut remaining = Vec::new();
+    for (path, issues) in files {
+        match resolve(root, &path, &issues, opts) {
+            Ok(Repair::None) => remaining.extend(issues),
+            Ok(Repair::Renamed) => remaining.extend(
+                issues
+                    .into_iter()
+                    .filter(|issue| !matches!(issue.problem, Problem::Renamable(_))),
+            ),
+            Ok(Repair::Quarantined) => {}
+            Err(e) => {
+                warn!("Failed to repair `{}`: {}", path.display(), e);
+                remaining.extend(issues);
+                remaining.push(Issue::new(&path, Problem::RepairFailed(e.to_string())));
+            }
+        }
+    }
+    Ok(remaining)
+}
+
+/// Check the presets, results and reports in the current directory
+pub fn run(opts: &ValidateOptions) -> DynResult<()> {
+    let remaining = validate(Path::new("."), opts)?;
+    if remaining.is_empty() {
+        info!("No problems found");
+        return Ok(());
+    }
+    print!(
+        "{}",
+        render_list(remaining.iter().map(ToString::to_string).collect())
+    );
+    rerr!(format!("Found {} problem(s)", remaining.len()))
+}
+
+#[test]
+fn test_fixed_name() {
+    assert_eq!(
+        fixed_name("result-11092021-074657\n.json"),
+        Some("result-11092021-074657.json".to_owned())
+    );
+    assert_eq!(fixed_name("result-11092021-074657.json"), None);
+    assert_eq!(fixed_name("result-1109 2021.json"), None);
+}
+
+#[test]
+fn test_validate() {
+    let root = std::env::temp_dir().join(format!("skyreport-validate-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for dir in [util::DIR_PRESET, util::DIR_RESULTS, util::DIR_REPORTS] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    let write = |path: &str, body: &str| fs::write(root.join(path), body).unwrap();
+    let result = r#"{"commit":"abc","pr":"222","raw":{"get":1,"set":1,"update":1},"results":[]}"#;
+    write(
+        "preset/next.json",
+        r#"{"commit":"abc","report":{"get":1,"set":1,"update":1}}"#,
+    );
+    write("preset/release.json", "");
+    write("results/result-11092021-074657\n.json", result);
+    write(
+        "reports/result-11092021-074657\n.md",
+        "# Skyreport\n- Pull request: [222]()",
+    );
+    write("results/result-11092021-081115.json", result);
+    write(
+        "reports/result-11092021-081115.md",
+        "# Skyreport\n- Pull request: [258]()",
+    );
+    write("results/result-11092021-092148.json", result);
+    write("results/garbage.json", "{}");
+    // an empty result with a bad name, and its report
+    write("results/result-11092021-101010\n.json", "");
+    write("reports/result-11092021-101010\n.md", "# Skyreport\n");
+
+    let check = |opts: &ValidateOptions| {
+        let mut problems: Vec<Problem> = validate(&root, opts)
+            .unwrap()
+            .into_iter()
+            .map(|issue| issue.problem)
+            .collect();
+        problems.sort_by_key(|p| format!("{:?}", p));
+        problems
+    };
+    let nofix = ValidateOptions {
+        fix: false,
+        quarantine: false,
+    };
+    assert_eq!(
+        check(&nofix),
+        vec![
+            Problem::BadName,
+            Problem::Corrupt("EOF while parsing a value at line 1 column 0".to_owned()),
+            Problem::Corrupt("EOF while parsing a value at line 1 column 0".to_owned()),
+            Problem::Mismatch("the report isn't for PR#222".to_owned()),
+            Problem::Orphan,
+            Problem::Orphan,
+            Problem::Renamable("result-11092021-074657.json".to_owned()),
+            Problem::Renamable("result-11092021-074657.md".to_owned()),
+            Problem::Renamable("result-11092021-101010.json".to_owned()),
+            Problem::Renamable("result-11092021-101010.md".to_owned()),
+        ]
+    );
+    let fix = ValidateOptions {
+        fix: true,
+        quarantine: true,
+    };
+    assert_eq!(
+        check(&fix),
+        vec![
+            Problem::Mismatch("the report isn't for PR#222".to_owned()),
+            Problem::Orphan,
+            Problem::Orphan
+        ]
+    );
+    assert!(root.join("results/result-11092021-074657.json").exists());
+    // the empty result is quarantined (and not renamed), and its report is renamed
+    assert!(root
+        .join("quarantine/results/result-11092021-101010\n.json")
+        .exists());
+    assert!(!root.join("results/result-11092021-101010.json").exists());
+    assert!(root.join("reports/result-11092021-101010.md").exists());
+    assert!(root.join("quarantine/results/garbage.json").exists());
+    assert!(root.join("quarantine/preset/release.json").exists());
+    // a repair that fails is reported, and the other files are still repaired
+    fs::remove_dir_all(root.join("quarantine/preset")).unwrap();
+    write("quarantine/preset", "");
+    write("preset/next.json", "");
+    write("reports/result-11092021-111111\n.md", "# Skyreport\n");
+    let remaining = validate(&root, &fix).unwrap();
+    assert!(remaining
+        .iter()
+        .any(|issue| matches!(issue.problem, Problem::RepairFailed(_))));
+    assert!(root.join("preset/next.json").exists());
+    assert!(root.join("reports/result-11092021-111111.md").exists());
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,219 @@
+/*
+ * Created on Sat Oct 17 2026
+ *
+ * This file is a part of Skytable's "Perf" tool
+ * Skytable's performance tool is used to analyze the performance of the
+ * Skytable database.
+ *
+ * Copyright (c) 2026, Sayan Nandan <ohsayan@outlook.com>
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program. If not, see <https://www.gnu.org/licenses/>.
+ *
+*/
+
+use crate::DynResult;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often we check if the child has exited
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A stage of the benchmark pipeline
+pub enum Stage {
+    /// Fetching the repo and checking out the ref
+    Checkout,
+    Build,
+    /// Starting the server and waiting for it to be ready
+    Startup,
+    /// Running `sky-bench`
+    Bench,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stage::Checkout => "checkout",
+            Stage::Build => "build",
+            Stage::Startup => "server startup",
+            Stage::Bench => "benchmark",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+/// Why a stage failed
+pub enum Failure {
+    TimedOut(Duration),
+    Failed(String),
+}
+
+#[derive(Debug, PartialEq)]
+/// An error that says which stage of the pipeline failed, and how
+pub struct StageError {
+    pub stage: Stage,
+    pub failure: Failure,
+}
+
+impl StageError {
+    pub const fn timed_out(stage: Stage, timeout: Duration) -> Self {
+        Self {
+            stage,
+            failure: Failure::TimedOut(timeout),
+        }
+    }
+}
+
+impl fmt::Display for StageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.failure {
+            Failure::TimedOut(timeout) => {
+                write!(
+                    f,
+                    "the {} timed out after {}s",
+                    self.stage,
+                    timeout.as_secs()
+                )
+            }
+            Failure::Failed(ref e) => write!(f, "the {} failed: {}", self.stage, e),
+        }
+    }
+}
+
+impl Error for StageError {}
+
+/// Run `f` as `stage`, so that any error that it returns says which stage failed
+pub fn in_stage<T>(stage: Stage, f: impl FnOnce() -> DynResult<T>) -> DynResult<T> {
+    f().map_err(|e| match e.downcast::<StageError>() {
+        // this was already classified (for example, the stage timed out)
+        Ok(e) => e as Box<dyn Error>,
+        Err(e) => Box::new(StageError {
+            stage,
+            failure: Failure::Failed(e.to_string()),
+        }),
+    })
+}
+
+/// Enforces a timeout on the commands run during a stage. Every command is run in its
+/// own process group, and the whole group is killed if the stage runs out of time
+pub struct Watchdog {
+    stage: Stage,
+    timeout: Duration,
+    deadline: Instant,
+}
+
+impl Watchdog {
+    /// Start the clock for `stage`. Every command run through the watchdog counts
+    /// towards the same `timeout`
+    pub fn new(stage: Stage, timeout: Duration) -> Self {
+        Self {
+            stage,
+            timeout,
+            deadline: Instant::now() + timeout,
+        }
+    }
+    /// Run `cmd` (with its output going to our output), failing if it doesn't succeed
+    pub fn run(&self, cmd: &mut Command) -> DynResult<()> {
+        let child = cmd.process_group(0).spawn()?;
+        let status = self.wait(child)?;
+        if !status.success() {
+            return rerr!(format!(
+                "`{}` failed with {}",
+                cmd.get_program().to_string_lossy(),
+                status
+            ));
+        }
+        Ok(())
+    }
+    /// Run `cmd` and collect its output (like [`Command::output`])
+    pub fn output(&self, cmd: &mut Command) -> DynResult<Output> {
+        let mut child = cmd
+            .process_group(0)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // read the pipes while we wait, so that the child doesn't block on a full pipe
+        let read = |mut pipe: Box<dyn Read + Send>| {
+            thread::spawn(move || {
+                let mut buf = Vec::new();
+                pipe.read_to_end(&mut buf).map(|_| buf)
+            })
+        };
+        let stdout = read(Box::new(child.stdout.take().unwrap()));
+        let stderr = read(Box::new(child.stderr.take().unwrap()));
+        let status = self.wait(child)?;
+        Ok(Output {
+            status,
+            stdout: stdout.join().unwrap()?,
+            stderr: stderr.join().unwrap()?,
+        })
+    }
+    fn wait(&self, mut child: Child) -> DynResult<ExitStatus> {
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+            if Instant::now() >= self.deadline {
+                warn!(
+                    "The {} timed out. Killing process group {}",
+                    self.stage,
+                    child.id()
+                );
+                // the child leads its own group, so this kills it and everything it spawned
+                let pgid = child.id() as libc::pid_t;
+                if unsafe { libc::kill(-pgid, libc::SIGKILL) } != 0 {
+                    warn!(
+                        "Failed to kill process group {}: {}",
+                        pgid,
+                        io::Error::last_os_error()
+                    );
+                    child.kill()?;
+                }
+                child.wait()?;
+                return Err(StageError::timed_out(self.stage, self.timeout).into());
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+#[test]
+fn test_watchdog() {
+    let watchdog = Watchdog::new(Stage::Bench, Duration::from_secs(10));
+    let output = watchdog.output(Command::new("echo").arg("hello")).unwrap();
+    assert_eq!(output.stdout, b"hello\n");
+    assert!(watchdog.run(&mut Command::new("false")).is_err());
+    // the grandchild is killed along with the child
+    let start = Instant::now();
+    let watchdog = Watchdog::new(Stage::Build, Duration::from_millis(200));
+    let err = watchdog
+        .output(Command::new("sh").args(["-c", "sleep 30 & wait"]))
+        .unwrap_err();
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(
+        *err.downcast::<StageError>().unwrap(),
+        StageError::timed_out(Stage::Build, Duration::from_millis(200))
+    );
+    let err = in_stage(Stage::Checkout, || -> DynResult<()> {
+        rerr!("no such ref")
+    })
+    .unwrap_err();
+    assert_eq!(err.to_string(), "the checkout failed: no such ref");
+}